@@ -1,56 +1,111 @@
 use bytemuck::{Pod, Zeroable};
-use graphics_samples::camera::Camera;
+use graphics_samples::camera::camera_path::{CameraPath, CameraPathMode};
+use graphics_samples::camera::orbit_camera::OrbitCamera;
+use graphics_samples::camera::{Camera, CameraSmoothing, CameraTrait};
+use graphics_samples::frame::Frame;
 use graphics_samples::graphics_context::GraphicsContext;
+use graphics_samples::input::Input;
 use graphics_samples::{SampleApp, SampleRequirements, SampleTrait};
 use nalgebra::Matrix4;
 use std::borrow::Cow;
+use std::path::Path;
 use std::time::Duration;
 use wgpu::naga::ShaderStage;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::{
-    Buffer, BufferAddress, BufferUsages, Color, ColorTargetState, ColorWrites,
-    CommandEncoderDescriptor, DeviceDescriptor, Features, FragmentState, FrontFace, Limits, LoadOp,
-    Operations, PipelineLayoutDescriptor, PrimitiveState, PrimitiveTopology, PushConstantRange,
-    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
-    ShaderModuleDescriptor, ShaderSource, ShaderStages, StoreOp, TextureView, VertexAttribute,
-    VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingType, Buffer, BufferAddress, BufferBindingType, BufferDescriptor,
+    BufferSize, BufferUsages, Color, ColorTargetState, ColorWrites, Features, FragmentState,
+    FrontFace, Limits, LoadOp, Operations, PipelineLayoutDescriptor, PrimitiveState,
+    PrimitiveTopology, PushConstantRange, RenderPassColorAttachment,
+    RenderPassDepthStencilAttachment, RenderPassDescriptor, RenderPipeline,
+    RenderPipelineDescriptor, ShaderModuleDescriptor, ShaderSource, ShaderStages, StoreOp,
+    TextureFormat, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
 };
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
 
 fn main() {
     env_logger::builder().format_timestamp(None).init();
 
     let sample_requirements = SampleRequirements {
-        device_descriptor: Some(DeviceDescriptor {
-            required_features: Features::PUSH_CONSTANTS,
-            required_limits: Limits {
-                // Matrix needs 64 bytes
-                max_push_constant_size: 64,
-                ..Default::default()
-            },
+        // Falls back to a uniform buffer if push constants are not supported
+        optional_features: Features::PUSH_CONSTANTS,
+        preferred_limits: Some(Limits {
+            // Matrix needs 64 bytes
+            max_push_constant_size: MATRIX_SIZE,
             ..Default::default()
         }),
+        depth_format: Some(TextureFormat::Depth32Float),
+        sample_count: 4,
+        restore_camera: true,
+        camera_bookmarks: true,
+        ..Default::default()
     };
     let mut sample_app = SampleApp::<SampleContext>::new("Camera", sample_requirements);
 
     sample_app.run();
 }
 
+const MATRIX_SIZE: u32 = 64;
+const CAMERA_PATH_FILE: &str = "camera_states/camera_path.txt";
+const TRIANGLE: [[f32; 3]; 3] = [[0.0, 0.5, 0.25], [0.5, -0.5, 0.25], [-0.5, -0.5, 0.25]];
+
 struct SampleContext {
     camera: Camera,
+    orbit_camera: OrbitCamera,
+    // Tab switches between the fly and the orbit camera
+    use_orbit_camera: bool,
+    // R records a flythrough of the fly camera, P plays it back
+    camera_path: CameraPath,
     vertex_buffer: Buffer,
+    matrix_binding: MatrixBinding,
     render_pipeline: RenderPipeline,
 }
 
+// How the MVP matrix is passed to the vertex shader
+enum MatrixBinding {
+    PushConstants,
+    UniformBuffer {
+        uniform_buffer: Buffer,
+        bind_group: BindGroup,
+    },
+}
+
 impl SampleTrait for SampleContext {
     fn new(graphics_context: &GraphicsContext) -> anyhow::Result<Self> {
-        let camera = Camera::new(
+        let mut camera = Camera::new(
             [0.0, 0.0, -1.0],
             [0.0, 0.0, 1.0],
             1.0,
             1.0,
-            graphics_context.window.current_monitor(),
+            graphics_context.current_monitor(),
+        );
+        camera.set_roll_enabled(true);
+        camera.set_smoothing(CameraSmoothing {
+            acceleration: 10.0,
+            damping: 8.0,
+            mouse_smoothing: 0.03,
+        });
+        let orbit_camera = OrbitCamera::new(
+            [0.0, 0.0, 0.0],
+            1.0,
+            0.0,
+            0.0,
+            1.0,
+            graphics_context.current_monitor(),
         );
 
+        let use_push_constants = graphics_context
+            .features()
+            .contains(Features::PUSH_CONSTANTS)
+            && graphics_context.limits().max_push_constant_size >= MATRIX_SIZE;
+        let shader_defines: &[(&str, &str)] = if use_push_constants {
+            &[("USE_PUSH_CONSTANTS", "1")]
+        } else {
+            &[]
+        };
+
         // Shaders
         let vertex_shader = graphics_context
             .device
@@ -63,9 +118,15 @@ impl SampleTrait for SampleContext {
 
                     layout(location = 0) in vec3 in_Position;
                     layout(location = 1) in vec4 in_Color;
+                    #ifdef USE_PUSH_CONSTANTS
                     layout(push_constant) uniform PushConstants {
                         mat4 mvp_matrix;
                     } p_c;
+                    #else
+                    layout(set = 0, binding = 0) uniform Uniforms {
+                        mat4 mvp_matrix;
+                    } p_c;
+                    #endif
                     out vec4 out_Color;
 
                     void main() {
@@ -75,7 +136,7 @@ impl SampleTrait for SampleContext {
                 "#,
                     ),
                     stage: ShaderStage::Vertex,
-                    defines: Default::default(),
+                    defines: shader_defines,
                 },
             });
 
@@ -112,15 +173,15 @@ impl SampleTrait for SampleContext {
 
         let vertexes = vec![
             Vertex {
-                position: [0.0, 0.5, 0.25],
+                position: TRIANGLE[0],
                 color: [1.0, 0.0, 0.0],
             },
             Vertex {
-                position: [0.5, -0.5, 0.25],
+                position: TRIANGLE[1],
                 color: [0.0, 1.0, 0.0],
             },
             Vertex {
-                position: [-0.5, -0.5, 0.25],
+                position: TRIANGLE[2],
                 color: [0.0, 0.0, 1.0],
             },
         ];
@@ -132,22 +193,77 @@ impl SampleTrait for SampleContext {
                 usage: BufferUsages::VERTEX,
             });
 
+        // Matrix binding
+        let (matrix_binding, pipeline_layout) = if use_push_constants {
+            let pipeline_layout =
+                graphics_context
+                    .device
+                    .create_pipeline_layout(&PipelineLayoutDescriptor {
+                        label: None,
+                        bind_group_layouts: &[],
+                        push_constant_ranges: &[PushConstantRange {
+                            stages: ShaderStages::VERTEX,
+                            range: 0..MATRIX_SIZE,
+                        }],
+                    });
+            (MatrixBinding::PushConstants, pipeline_layout)
+        } else {
+            let uniform_buffer = graphics_context.device.create_buffer(&BufferDescriptor {
+                label: None,
+                size: MATRIX_SIZE as BufferAddress,
+                usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            let bind_group_layout =
+                graphics_context
+                    .device
+                    .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                        label: None,
+                        entries: &[BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: ShaderStages::VERTEX,
+                            ty: BindingType::Buffer {
+                                ty: BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: BufferSize::new(MATRIX_SIZE as u64),
+                            },
+                            count: None,
+                        }],
+                    });
+            let bind_group = graphics_context
+                .device
+                .create_bind_group(&BindGroupDescriptor {
+                    label: None,
+                    layout: &bind_group_layout,
+                    entries: &[BindGroupEntry {
+                        binding: 0,
+                        resource: uniform_buffer.as_entire_binding(),
+                    }],
+                });
+            let pipeline_layout =
+                graphics_context
+                    .device
+                    .create_pipeline_layout(&PipelineLayoutDescriptor {
+                        label: None,
+                        bind_group_layouts: &[&bind_group_layout],
+                        push_constant_ranges: &[],
+                    });
+            (
+                MatrixBinding::UniformBuffer {
+                    uniform_buffer,
+                    bind_group,
+                },
+                pipeline_layout,
+            )
+        };
+
         // Render Pipeline
         let render_pipeline =
             graphics_context
                 .device
                 .create_render_pipeline(&RenderPipelineDescriptor {
                     label: None,
-                    layout: Some(&graphics_context.device.create_pipeline_layout(
-                        &PipelineLayoutDescriptor {
-                            label: None,
-                            bind_group_layouts: &[],
-                            push_constant_ranges: &[PushConstantRange {
-                                stages: ShaderStages::VERTEX,
-                                range: 0..64,
-                            }],
-                        },
-                    )),
+                    layout: Some(&pipeline_layout),
                     vertex: VertexState {
                         module: &vertex_shader,
                         entry_point: Some("main"),
@@ -178,17 +294,14 @@ impl SampleTrait for SampleContext {
                         polygon_mode: Default::default(),
                         conservative: false,
                     },
-                    depth_stencil: None,
-                    multisample: Default::default(),
+                    depth_stencil: graphics_context.depth_stencil_state(true),
+                    multisample: graphics_context.multisample_state(),
                     fragment: Some(FragmentState {
                         module: &fragment_shader,
                         entry_point: Some("main"),
                         compilation_options: Default::default(),
                         targets: &[Some(ColorTargetState {
-                            format: graphics_context
-                                .surface_data
-                                .surface_configuration
-                                .view_formats[0],
+                            format: graphics_context.color_format(),
                             blend: None,
                             write_mask: ColorWrites::all(),
                         })],
@@ -199,73 +312,139 @@ impl SampleTrait for SampleContext {
 
         Ok(Self {
             camera,
+            orbit_camera,
+            use_orbit_camera: false,
+            camera_path: CameraPath::default(),
             vertex_buffer,
+            matrix_binding,
             render_pipeline,
         })
     }
 
-    fn render(
-        &mut self,
-        graphics_context: &GraphicsContext,
-        surface_texture_view: TextureView,
-        frame_time_delta: Duration,
-    ) {
-        let mut command_encoder = graphics_context
-            .device
-            .create_command_encoder(&CommandEncoderDescriptor::default());
+    fn on_input(&mut self, graphics_context: &GraphicsContext, input: &Input) {
+        // Clicking the triangle logs where it was hit
+        if input.is_mouse_button_just_pressed(MouseButton::Left)
+            && let Some(cursor_position) = input.cursor_position()
+            && let Some(ray) = self
+                .active_camera()
+                .ray_from_cursor(cursor_position, graphics_context.target_size())
+            && let Some(distance) = ray.intersect_triangle(TRIANGLE[0], TRIANGLE[1], TRIANGLE[2])
+        {
+            let hit = ray.at(distance);
+            log::info!(
+                "Triangle hit at ({:.3}, {:.3}, {:.3}), distance {distance:.3}",
+                hit.x,
+                hit.y,
+                hit.z
+            );
+        }
+
+        if input.is_key_just_pressed(KeyCode::Tab) {
+            self.use_orbit_camera = !self.use_orbit_camera;
+        }
+
+        if input.is_key_just_pressed(KeyCode::KeyR) {
+            if self.camera_path.mode() == CameraPathMode::Recording {
+                self.camera_path.stop(&self.camera);
+                if let Err(err) = self.camera_path.save(Path::new(CAMERA_PATH_FILE)) {
+                    log::error!("{err:#}");
+                }
+            } else {
+                self.use_orbit_camera = false;
+                self.camera_path.start_recording();
+            }
+        }
+        if input.is_key_just_pressed(KeyCode::KeyP) {
+            if self.camera_path.mode() == CameraPathMode::Playing {
+                self.camera_path.stop(&self.camera);
+            } else {
+                if self.camera_path.keyframes.is_empty() {
+                    match CameraPath::load(Path::new(CAMERA_PATH_FILE)) {
+                        Ok(camera_path) => self.camera_path = camera_path,
+                        Err(err) => log::error!("{err:#}"),
+                    }
+                }
+                self.use_orbit_camera = false;
+                self.camera_path.start_playback();
+            }
+        }
+    }
+
+    fn update(&mut self, update_timestep: Duration) {
+        if self.use_orbit_camera {
+            self.orbit_camera.update(update_timestep);
+        } else {
+            self.camera_path.update(&mut self.camera, update_timestep);
+        }
+    }
+
+    fn render(&mut self, graphics_context: &GraphicsContext, frame: &mut Frame) {
+        // Projection follows the window size and SampleRequirements::reverse_z, see SampleApp
+        let view_projection_matrix = self
+            .active_camera()
+            .view_projection_matrix(frame.interpolation);
+        let model_matrix = Matrix4::<f32>::identity();
+        let mvp_matrix = view_projection_matrix * model_matrix;
+
+        if let MatrixBinding::UniformBuffer { uniform_buffer, .. } = &self.matrix_binding {
+            graphics_context
+                .queue
+                .write_buffer(uniform_buffer, 0, bytemuck::bytes_of(&mvp_matrix));
+        }
+
         {
-            let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+            let mut render_pass = frame.encoder.begin_render_pass(&RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &surface_texture_view,
-                    resolve_target: None,
+                    view: &frame.view,
+                    resolve_target: frame.resolve_target.as_ref(),
                     ops: Operations {
                         load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
+                        store: frame.color_store_op(),
                     },
                 })],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachment {
+                    view: frame.depth_view.as_ref().unwrap(),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(graphics_context.depth_clear_value()),
+                        store: StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
                 timestamp_writes: None,
                 occlusion_query_set: None,
             });
             render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
             render_pass.set_pipeline(&self.render_pipeline);
 
-            // Camera
-            // nalgebra creates a projection matrix for OpenGL, but it is not suitable for wgpu because:
-            // 1. Incorrect Z-axis direction
-            // 2. Incorrect depth clip space
-            // OpenGL: [-1,1], wgpu: [0,1]
-            #[rustfmt::skip]
-            let projection_correction = Matrix4::new(
-                1.0, 0.0, 0.0, 0.0,
-                0.0, 1.0, 0.0, 0.0,
-                0.0, 0.0, -0.5, 0.5,
-                0.0, 0.0, -1.0, 0.0,
-            );
-            let projection_matrix = Matrix4::new_perspective(
-                graphics_context.window_aspect(),
-                45.0_f32.to_radians(),
-                0.1,
-                100.0,
-            );
-            let projection_matrix = projection_correction * projection_matrix;
-            let view_matrix = self.camera.calculate_view_matrix(frame_time_delta);
-            let model_matrix = Matrix4::<f32>::identity();
-            let mvp_matrix = projection_matrix * view_matrix * model_matrix;
-            render_pass.set_push_constants(
-                ShaderStages::VERTEX,
-                0,
-                bytemuck::bytes_of(&mvp_matrix),
-            );
+            match &self.matrix_binding {
+                MatrixBinding::PushConstants => {
+                    render_pass.set_push_constants(
+                        ShaderStages::VERTEX,
+                        0,
+                        bytemuck::bytes_of(&mvp_matrix),
+                    );
+                }
+                MatrixBinding::UniformBuffer { bind_group, .. } => {
+                    render_pass.set_bind_group(0, bind_group, &[]);
+                }
+            }
 
             render_pass.draw(0..3, 0..1);
         }
-        let command_buffer = command_encoder.finish();
-        graphics_context.queue.submit([command_buffer]);
     }
 
-    fn process_camera_input(&mut self) -> Option<&mut Camera> {
-        Some(&mut self.camera)
+    fn process_camera_input(&mut self) -> Option<&mut dyn CameraTrait> {
+        Some(self.active_camera())
+    }
+}
+
+impl SampleContext {
+    fn active_camera(&mut self) -> &mut dyn CameraTrait {
+        if self.use_orbit_camera {
+            &mut self.orbit_camera
+        } else {
+            &mut self.camera
+        }
     }
 }
@@ -1,16 +1,16 @@
 use bytemuck::{Pod, Zeroable};
+use graphics_samples::frame::Frame;
 use graphics_samples::graphics_context::GraphicsContext;
 use graphics_samples::{SampleApp, SampleRequirements, SampleTrait};
 use std::borrow::Cow;
-use std::time::Duration;
 use wgpu::naga::ShaderStage;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::{
-    Buffer, BufferAddress, BufferUsages, Color, ColorTargetState, ColorWrites,
-    CommandEncoderDescriptor, FragmentState, FrontFace, LoadOp, Operations, PrimitiveState,
-    PrimitiveTopology, RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
-    RenderPipelineDescriptor, ShaderModule, ShaderModuleDescriptor, ShaderSource, StoreOp,
-    TextureView, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+    Buffer, BufferAddress, BufferUsages, Color, ColorTargetState, ColorWrites, FragmentState,
+    FrontFace, LoadOp, Operations, PrimitiveState, PrimitiveTopology, RenderPassColorAttachment,
+    RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor, ShaderModule,
+    ShaderModuleDescriptor, ShaderSource, VertexAttribute, VertexBufferLayout, VertexFormat,
+    VertexState, VertexStepMode,
 };
 
 fn main() {
@@ -25,7 +25,9 @@ fn main() {
 }
 
 struct SampleContext {
+    #[allow(unused)]
     vertex_shader: ShaderModule,
+    #[allow(unused)]
     fragment_shader: ShaderModule,
     vertex_buffer: Buffer,
     render_pipeline: RenderPipeline,
@@ -149,16 +151,13 @@ impl SampleTrait for SampleContext {
                         conservative: false,
                     },
                     depth_stencil: None,
-                    multisample: Default::default(),
+                    multisample: graphics_context.multisample_state(),
                     fragment: Some(FragmentState {
                         module: &fragment_shader,
                         entry_point: Some("main"),
                         compilation_options: Default::default(),
                         targets: &[Some(ColorTargetState {
-                            format: graphics_context
-                                .surface_data
-                                .surface_configuration
-                                .view_formats[0],
+                            format: graphics_context.color_format(),
                             blend: None,
                             write_mask: ColorWrites::all(),
                         })],
@@ -175,24 +174,16 @@ impl SampleTrait for SampleContext {
         })
     }
 
-    fn render(
-        &mut self,
-        graphics_context: &GraphicsContext,
-        surface_texture_view: TextureView,
-        _frame_time_delta: Duration,
-    ) {
-        let mut command_encoder = graphics_context
-            .device
-            .create_command_encoder(&CommandEncoderDescriptor::default());
+    fn render(&mut self, _graphics_context: &GraphicsContext, frame: &mut Frame) {
         {
-            let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+            let mut render_pass = frame.encoder.begin_render_pass(&RenderPassDescriptor {
                 label: None,
                 color_attachments: &[Some(RenderPassColorAttachment {
-                    view: &surface_texture_view,
-                    resolve_target: None,
+                    view: &frame.view,
+                    resolve_target: frame.resolve_target.as_ref(),
                     ops: Operations {
                         load: LoadOp::Clear(Color::BLACK),
-                        store: StoreOp::Store,
+                        store: frame.color_store_op(),
                     },
                 })],
                 depth_stencil_attachment: None,
@@ -203,7 +194,5 @@ impl SampleTrait for SampleContext {
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.draw(0..3, 0..1);
         }
-        let command_buffer = command_encoder.finish();
-        graphics_context.queue.submit([command_buffer]);
     }
 }
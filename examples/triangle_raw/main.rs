@@ -1,19 +1,24 @@
-use anyhow::Context;
+use anyhow::{Context, bail};
 use bytemuck::{Pod, Zeroable};
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, mpsc};
 use wgpu::naga::ShaderStage;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
 use wgpu::{
-    Adapter, Backends, Buffer, BufferAddress, BufferUsages, Color, ColorTargetState, ColorWrites,
-    CommandEncoderDescriptor, CompositeAlphaMode, Device, DeviceDescriptor, FragmentState,
-    FrontFace, Instance, InstanceDescriptor, LoadOp, MemoryHints, Operations, PollType,
+    Adapter, Backends, Buffer, BufferAddress, BufferDescriptor, BufferUsages,
+    COPY_BYTES_PER_ROW_ALIGNMENT, Color, ColorTargetState, ColorWrites, CommandEncoderDescriptor,
+    CompositeAlphaMode, Device, DeviceDescriptor, DeviceType, Extent3d, FragmentState, FrontFace,
+    Instance, InstanceDescriptor, LoadOp, MapMode, MemoryHints, Operations, Origin3d, PollType,
     PowerPreference, PresentMode, PrimitiveState, PrimitiveTopology, Queue,
     RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
     RequestAdapterOptions, ShaderModule, ShaderModuleDescriptor, ShaderSource, StoreOp, Surface,
-    SurfaceCapabilities, SurfaceConfiguration, SurfaceError, SurfaceTexture, TextureAspect,
-    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension, VertexAttribute,
-    VertexBufferLayout, VertexFormat, VertexState, VertexStepMode,
+    SurfaceCapabilities, SurfaceConfiguration, SurfaceError, SurfaceTexture, TexelCopyBufferInfo,
+    TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
+    TextureViewDimension, VertexAttribute, VertexBufferLayout, VertexFormat, VertexState,
+    VertexStepMode,
 };
 use winit::application::ApplicationHandler;
 use winit::dpi::LogicalSize;
@@ -24,6 +29,16 @@ use winit::window::{Window, WindowId};
 fn main() {
     env_logger::builder().format_timestamp(None).init();
 
+    // Same flags as the samples built on SampleApp: --headless --frames <N> --size <W>x<H> --output <PATH>
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--headless") {
+        if let Err(err) = run_headless(&args) {
+            log::error!("Failed to run headless: {err:#}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     let event_loop = EventLoop::new().expect("Failed to create event loop");
     event_loop.set_control_flow(ControlFlow::Poll);
 
@@ -90,7 +105,13 @@ impl ApplicationHandler for App {
             }
         };
 
-        let app_context = AppContext::new(&graphics_context);
+        let app_context = AppContext::new(
+            &graphics_context.device,
+            graphics_context
+                .surface_data
+                .surface_configuration
+                .view_formats[0],
+        );
         let app_context = match app_context {
             Ok(app_context) => app_context,
             Err(err) => {
@@ -152,9 +173,9 @@ impl GraphicsContext {
         );
         window.set_min_inner_size(Some(LogicalSize::new(1, 1)));
 
-        // Instance
+        // Instance, WGPU_BACKEND overrides the backends
         let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::PRIMARY,
+            backends: Backends::PRIMARY.with_env(),
             ..Default::default()
         });
 
@@ -164,13 +185,7 @@ impl GraphicsContext {
             .context("Failed to create surface")?;
 
         // Adapter
-        let adapter =
-            futures::executor::block_on(instance.request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            }))
-            .context("Failed to request adapter")?;
+        let adapter = request_adapter(&instance, Some(&surface))?;
         log::info!(
             "Selected adapter: {}, {}",
             adapter.get_info().name,
@@ -330,22 +345,22 @@ impl SurfaceData {
 }
 
 struct AppContext {
+    #[allow(unused)]
     vertex_shader: ShaderModule,
+    #[allow(unused)]
     fragment_shader: ShaderModule,
     vertex_buffer: Buffer,
     render_pipeline: RenderPipeline,
 }
 
 impl AppContext {
-    pub fn new(graphics_context: &GraphicsContext) -> anyhow::Result<Self> {
+    pub fn new(device: &Device, color_format: TextureFormat) -> anyhow::Result<Self> {
         // Shaders
-        let vertex_shader = graphics_context
-            .device
-            .create_shader_module(ShaderModuleDescriptor {
-                label: None,
-                source: ShaderSource::Glsl {
-                    shader: Cow::Borrowed(
-                        r#"
+        let vertex_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(
+                    r#"
                     #version 460
 
                     layout(location = 0) in vec3 in_Position;
@@ -357,19 +372,16 @@ impl AppContext {
                         out_Color = in_Color;
                     }
                 "#,
-                    ),
-                    stage: ShaderStage::Vertex,
-                    defines: Default::default(),
-                },
-            });
-        let fragment_shader =
-            graphics_context
-                .device
-                .create_shader_module(ShaderModuleDescriptor {
-                    label: None,
-                    source: ShaderSource::Glsl {
-                        shader: Cow::Borrowed(
-                            r#"
+                ),
+                stage: ShaderStage::Vertex,
+                defines: Default::default(),
+            },
+        });
+        let fragment_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: None,
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(
+                    r#"
                     #version 460
 
                     in vec4 out_Color;
@@ -379,11 +391,11 @@ impl AppContext {
                         frag_Color = out_Color;
                     }
                 "#,
-                        ),
-                        stage: ShaderStage::Fragment,
-                        defines: Default::default(),
-                    },
-                });
+                ),
+                stage: ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+        });
 
         // Vertex buffer
         #[repr(C)]
@@ -407,69 +419,61 @@ impl AppContext {
                 color: [0.0, 0.0, 1.0],
             },
         ];
-        let vertex_buffer = graphics_context
-            .device
-            .create_buffer_init(&BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(&vertexes),
-                usage: BufferUsages::VERTEX,
-            });
+        let vertex_buffer = device.create_buffer_init(&BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&vertexes),
+            usage: BufferUsages::VERTEX,
+        });
 
         // Render Pipeline
-        let render_pipeline =
-            graphics_context
-                .device
-                .create_render_pipeline(&RenderPipelineDescriptor {
-                    label: None,
-                    layout: None,
-                    vertex: VertexState {
-                        module: &vertex_shader,
-                        entry_point: Some("main"),
-                        compilation_options: Default::default(),
-                        buffers: &[VertexBufferLayout {
-                            array_stride: size_of::<Vertex>() as BufferAddress,
-                            step_mode: VertexStepMode::Vertex,
-                            attributes: &[
-                                VertexAttribute {
-                                    format: VertexFormat::Float32x3,
-                                    offset: 0,
-                                    shader_location: 0,
-                                },
-                                VertexAttribute {
-                                    format: VertexFormat::Float32x3,
-                                    offset: 4 * 3,
-                                    shader_location: 1,
-                                },
-                            ],
-                        }],
-                    },
-                    primitive: PrimitiveState {
-                        topology: PrimitiveTopology::TriangleList,
-                        strip_index_format: None,
-                        front_face: FrontFace::Cw,
-                        cull_mode: None,
-                        unclipped_depth: false,
-                        polygon_mode: Default::default(),
-                        conservative: false,
-                    },
-                    depth_stencil: None,
-                    multisample: Default::default(),
-                    fragment: Some(FragmentState {
-                        module: &fragment_shader,
-                        entry_point: Some("main"),
-                        compilation_options: Default::default(),
-                        targets: &[Some(ColorTargetState {
-                            format: graphics_context
-                                .surface_data
-                                .surface_configuration
-                                .view_formats[0],
-                            blend: None,
-                            write_mask: ColorWrites::all(),
-                        })],
-                    }),
-                    multiview: None,
-                    cache: None,
-                });
+        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: VertexState {
+                module: &vertex_shader,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                buffers: &[VertexBufferLayout {
+                    array_stride: size_of::<Vertex>() as BufferAddress,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: &[
+                        VertexAttribute {
+                            format: VertexFormat::Float32x3,
+                            offset: 0,
+                            shader_location: 0,
+                        },
+                        VertexAttribute {
+                            format: VertexFormat::Float32x3,
+                            offset: 4 * 3,
+                            shader_location: 1,
+                        },
+                    ],
+                }],
+            },
+            primitive: PrimitiveState {
+                topology: PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: FrontFace::Cw,
+                cull_mode: None,
+                unclipped_depth: false,
+                polygon_mode: Default::default(),
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(FragmentState {
+                module: &fragment_shader,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: color_format,
+                    blend: None,
+                    write_mask: ColorWrites::all(),
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
 
         Ok(Self {
             vertex_shader,
@@ -479,3 +483,201 @@ impl AppContext {
         })
     }
 }
+
+// Adapter selection like in the samples built on SampleApp:
+// WGPU_POWER_PREF, WGPU_FORCE_FALLBACK_ADAPTER and WGPU_ADAPTER_NAME override the defaults
+fn request_adapter(
+    instance: &Instance,
+    compatible_surface: Option<&Surface>,
+) -> anyhow::Result<Adapter> {
+    let power_preference = PowerPreference::from_env().unwrap_or(PowerPreference::HighPerformance);
+    let force_fallback_adapter = std::env::var("WGPU_FORCE_FALLBACK_ADAPTER")
+        .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true"));
+
+    let adapter = match std::env::var("WGPU_ADAPTER_NAME") {
+        Ok(adapter_name) => {
+            let adapter_name = adapter_name.to_lowercase();
+            instance
+                .enumerate_adapters(Backends::PRIMARY.with_env())
+                .into_iter()
+                .find(|adapter| {
+                    let info = adapter.get_info();
+                    info.name.to_lowercase().contains(&adapter_name)
+                        && (!force_fallback_adapter || info.device_type == DeviceType::Cpu)
+                        && compatible_surface
+                            .is_none_or(|surface| adapter.is_surface_supported(surface))
+                })
+                .with_context(|| {
+                    format!("No suitable adapter matches the name \"{adapter_name}\"")
+                })?
+        }
+        Err(_) => futures::executor::block_on(instance.request_adapter(&RequestAdapterOptions {
+            power_preference,
+            force_fallback_adapter,
+            compatible_surface,
+        }))
+        .context("Failed to request adapter")?,
+    };
+    Ok(adapter)
+}
+
+// Renders to a texture instead of a window surface and saves the last frame as PNG
+fn run_headless(args: &[String]) -> anyhow::Result<()> {
+    let mut frame_count = 60;
+    let (mut width, mut height) = (1280, 720);
+    let mut output_path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => {}
+            "--frames" => {
+                frame_count = args.next().context("--frames requires a value")?.parse()?;
+            }
+            "--size" => {
+                let (w, h) = args
+                    .next()
+                    .and_then(|value| value.split_once('x'))
+                    .context("--size requires a value <W>x<H>")?;
+                width = w.parse()?;
+                height = h.parse()?;
+            }
+            "--output" => output_path = Some(args.next().context("--output requires a value")?),
+            _ => bail!("Unknown argument: {arg}"),
+        }
+    }
+
+    // Instance, WGPU_BACKEND overrides the backends
+    let instance = Instance::new(&InstanceDescriptor {
+        backends: Backends::PRIMARY.with_env(),
+        ..Default::default()
+    });
+
+    // Adapter, there is no surface to be compatible with
+    let adapter = request_adapter(&instance, None)?;
+    log::info!(
+        "Selected adapter: {}, {}",
+        adapter.get_info().name,
+        adapter.get_info().backend
+    );
+
+    // Device and Queue
+    let (device, queue) = futures::executor::block_on(adapter.request_device(&DeviceDescriptor {
+        label: None,
+        memory_hints: MemoryHints::MemoryUsage,
+        ..Default::default()
+    }))
+    .context("Failed to request device")?;
+
+    // Render target, COPY_SRC to read it back
+    let texture = device.create_texture(&TextureDescriptor {
+        label: None,
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8UnormSrgb,
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let texture_view = texture.create_view(&TextureViewDescriptor::default());
+
+    let app_context = AppContext::new(&device, texture.format())?;
+
+    for _ in 0..frame_count {
+        let mut command_encoder =
+            device.create_command_encoder(&CommandEncoderDescriptor::default());
+        {
+            let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(RenderPassColorAttachment {
+                    view: &texture_view,
+                    resolve_target: None,
+                    ops: Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_vertex_buffer(0, app_context.vertex_buffer.slice(..));
+            render_pass.set_pipeline(&app_context.render_pipeline);
+            render_pass.draw(0..3, 0..1);
+        }
+        queue.submit([command_encoder.finish()]);
+    }
+
+    let Some(output_path) = output_path else {
+        return Ok(());
+    };
+
+    // Copy the texture to a buffer, its rows must be aligned to COPY_BYTES_PER_ROW_ALIGNMENT
+    let unpadded_bytes_per_row = width * 4;
+    let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+        * COPY_BYTES_PER_ROW_ALIGNMENT;
+    let buffer = device.create_buffer(&BufferDescriptor {
+        label: None,
+        size: padded_bytes_per_row as u64 * height as u64,
+        usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let mut command_encoder = device.create_command_encoder(&CommandEncoderDescriptor::default());
+    command_encoder.copy_texture_to_buffer(
+        TexelCopyTextureInfo {
+            texture: &texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        TexelCopyBufferInfo {
+            buffer: &buffer,
+            layout: TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        texture.size(),
+    );
+    queue.submit([command_encoder.finish()]);
+
+    // Map the buffer and strip the row padding
+    let (sender, receiver) = mpsc::channel();
+    buffer.map_async(MapMode::Read, .., move |result| {
+        let _ = sender.send(result);
+    });
+    device
+        .poll(PollType::Wait)
+        .context("Failed to wait for the copy")?;
+    receiver
+        .recv()
+        .context("Buffer mapping was cancelled")?
+        .context("Failed to map buffer")?;
+    let mut rgba = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    for row in buffer
+        .get_mapped_range(..)
+        .chunks_exact(padded_bytes_per_row as usize)
+    {
+        rgba.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+    }
+    buffer.unmap();
+
+    // Rgba8UnormSrgb holds sRGB encoded values, which is what PNG expects
+    let file =
+        File::create(output_path).with_context(|| format!("Failed to create {output_path}"))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&rgba)?;
+    writer.finish()?;
+
+    Ok(())
+}
@@ -1,10 +1,24 @@
 // Camera for wgpu
 // Left-handed coordinate system
 
+pub mod camera_path;
+pub mod camera_state;
+pub mod frustum;
+pub mod orbit_camera;
+pub mod projection;
+pub mod ray;
+
+use crate::camera::camera_state::CameraState;
+use crate::camera::frustum::Frustum;
+use crate::camera::projection::Projection;
+use crate::camera::ray::Ray;
+use crate::input::Input;
+use crate::input::action_map::{
+    CAMERA_ROTATE, MOVE_BACK, MOVE_DOWN, MOVE_FAST, MOVE_FORWARD, MOVE_LEFT, MOVE_RIGHT, MOVE_SLOW,
+    MOVE_UP, ROLL_LEFT, ROLL_RIGHT,
+};
 use nalgebra::{Matrix4, UnitQuaternion, Vector3};
 use std::time::Duration;
-use winit::event::{ElementState, MouseButton};
-use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::monitor::MonitorHandle;
 
 // For sensitivity correction
@@ -13,10 +27,88 @@ use winit::monitor::MonitorHandle;
 const STANDARD_SCREEN_SIZE: (u32, u32) = (2560, 1440);
 const STANDARD_SCREEN_SIZE_COEFFICIENT: f32 = 0.15;
 
+// Move speed change per mouse wheel line
+const SCROLL_SPEED_FACTOR: f32 = 1.1;
+const MIN_MOVE_SPEED: f32 = 0.001;
+const MAX_MOVE_SPEED: f32 = 1000.0;
+
 type Vec3 = Vector3<f32>;
 
+// Implemented by every camera, so SampleApp can feed input to whichever one a sample uses
+pub trait CameraTrait {
+    // Called by SampleApp once per frame, see input::action_map for the actions
+    fn process_input(&mut self, input: &Input);
+
+    // Moves the camera, call from SampleTrait::update
+    fn update(&mut self, _update_timestep: Duration) {}
+
+    // Left-handed view matrix, interpolated between the last two updates, see Frame::interpolation
+    fn view_matrix(&self, interpolation: f32) -> Matrix4<f32>;
+
+    fn projection(&self) -> &Projection;
+    fn projection_mut(&mut self) -> &mut Projection;
+
+    // Follows the render target size and SampleRequirements::reverse_z, called by SampleApp every frame
+    // before SampleTrait::on_input, so picking there already uses the current aspect
+    fn sync_projection(&mut self, target_size: (u32, u32), reverse_z: bool) {
+        let projection = self.projection_mut();
+        projection.set_size(target_size.0, target_size.1);
+        projection.reverse_z = reverse_z;
+    }
+
+    fn view_projection_matrix(&self, interpolation: f32) -> Matrix4<f32> {
+        self.projection().matrix() * self.view_matrix(interpolation)
+    }
+
+    // World space frustum of the view that is rendered with the same interpolation
+    fn frustum(&self, interpolation: f32) -> Frustum {
+        Frustum::new(&self.view_matrix(interpolation), self.projection())
+    }
+
+    // World space ray through the cursor, see input::Input::cursor_position and GraphicsContext::target_size
+    // Uses the latest camera state, as the cursor is handled before the next update
+    fn ray_from_cursor(&self, cursor_position: (f64, f64), target_size: (u32, u32)) -> Option<Ray> {
+        Ray::from_screen(
+            cursor_position,
+            target_size,
+            &self.view_matrix(1.0),
+            self.projection(),
+        )
+    }
+
+    // Viewpoint and settings, see camera_state for the file format
+    fn state(&self) -> CameraState;
+    // Jumps to the state without interpolation or smoothing,
+    // the aspect and reverse_z of the projection are kept, see Projection::set_shape
+    fn set_state(&mut self, state: &CameraState);
+}
+
+// Optional smoothing of Camera movement and mouse look, the default disables it
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CameraSmoothing {
+    // Rate in 1/s at which the velocity approaches the move speed in the pressed direction,
+    // 0 moves at full speed immediately
+    pub acceleration: f32,
+    // Rate in 1/s at which the velocity decays once no move key is pressed, 0 stops immediately
+    pub damping: f32,
+    // Time constant in seconds of the exponential mouse look filter, 0 applies mouse motion immediately
+    pub mouse_smoothing: f32,
+}
+
+// Direction of the move_up and move_down actions
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalMovement {
+    // Along the world Y axis, independent of where the camera looks
+    #[default]
+    World,
+    // Along the camera up vector
+    Camera,
+}
+
 pub struct Camera {
     position: Vec3,
+    // Position before the last update, for interpolation between updates
+    previous_position: Vec3,
     // Normalized front(direction) vector
     front: Vec3,
     // Normalized right vector
@@ -27,17 +119,40 @@ pub struct Camera {
     yaw: f32,
     // Pitch angle in degrees (along Y axis)
     pitch: f32,
+    // Roll angle in degrees (along the front vector), positive rolls to the right
+    roll: f32,
     // Settings
     sensitivity: f32,
+    // Units per second, adjusted with the mouse wheel
     move_speed: f32,
+    // Move speed multipliers while move_fast or move_slow is pressed
+    fast_multiplier: f32,
+    slow_multiplier: f32,
+    vertical_movement: VerticalMovement,
+    // Roll is disabled by default, the horizon stays level
+    roll_enabled: bool,
+    // Degrees per second
+    roll_speed: f32,
+    smoothing: CameraSmoothing,
+    // Units per second, only differs from the pressed direction while smoothing
+    velocity: Vec3,
+    // Mouse rotation in degrees not yet applied by the mouse look filter
+    pending_yaw: f32,
+    pending_pitch: f32,
     // Screen size coefficient for sensitivity correction
     screen_size_coefficient: f32,
+    projection: Projection,
     // Input
     move_forward: bool,
     move_back: bool,
     move_right: bool,
     move_left: bool,
-    lmb_is_pressed: bool,
+    move_up: bool,
+    move_down: bool,
+    move_fast: bool,
+    move_slow: bool,
+    roll_left: bool,
+    roll_right: bool,
 }
 
 impl Camera {
@@ -48,102 +163,84 @@ impl Camera {
         move_speed: f32,
         current_monitor: Option<MonitorHandle>,
     ) -> Self {
-        let screen_size_coefficient = if let Some(current_monitor) = current_monitor {
-            // Corrects standard screen size coefficient to current monitor
-            // On a large screen, the mouse moves a greater distance in pixels than on a small screen, for the same physical mouse offset.
-            // Example
-            // Small screen: 1 cm (mouse) = 20 pixels = 20 degrees (coefficient 1)
-            // Large screen: 1 cm (mouse) = 40 pixels = 20 degrees (coefficient 0.5)
-            // Example
-            // Standard monitor: 2560x1440
-            // Standard screen size coefficient: 0.5
-            // 1.
-            // Current monitor: 1920x1080
-            // Current to standard ratio: 0.75
-            // Screen coefficient: 0.5 * (2 - 0.75) = 0.625
-            // 2.
-            // Current monitor: 3200x1800
-            // Current to standard ratio: 1.25
-            // Screen coefficient: 0.5 * (2 - 1.25) = 0.375
-            // 3.
-            // Current monitor: 5120x2880
-            // Current to standard ratio: 2.0
-            // Screen coefficient: 0.25
-
-            // Calculate monitor size coefficient
-            let difference_x = current_monitor.size().width as f32 / STANDARD_SCREEN_SIZE.0 as f32;
-
-            let scale_factor = if difference_x < 2.0 {
-                2.0 - difference_x
-            } else {
-                1.0 / difference_x
-            };
-
-            STANDARD_SCREEN_SIZE_COEFFICIENT * scale_factor
-        } else {
-            STANDARD_SCREEN_SIZE_COEFFICIENT
-        };
+        let screen_size_coefficient = screen_size_coefficient(current_monitor);
 
         let front: Vec3 = Vec3::from(front).normalize();
         let right: Vec3 = Vec3::normalize(&Vec3::cross(&Vec3::y_axis(), &front));
         let up: Vec3 = Vec3::normalize(&Vec3::cross(&front, &right));
 
         let yaw = front.x.atan2(front.z).to_degrees();
-        let pitch = front.y.asin().to_degrees();
+        // Positive pitch looks down (rotating +Z around +X), so the sign of front.y is flipped,
+        // otherwise update_vectors would turn a camera created looking up to look down
+        let pitch = (-front.y).asin().to_degrees();
 
-        Self {
+        let mut camera = Self {
             position: position.into(),
+            previous_position: position.into(),
             front,
             right,
             up,
             yaw,
             pitch,
+            roll: 0.0,
             sensitivity,
             move_speed,
+            fast_multiplier: 4.0,
+            slow_multiplier: 0.25,
+            vertical_movement: VerticalMovement::World,
+            roll_enabled: false,
+            roll_speed: 90.0,
+            smoothing: CameraSmoothing::default(),
+            velocity: Vec3::zeros(),
+            pending_yaw: 0.0,
+            pending_pitch: 0.0,
             screen_size_coefficient,
+            projection: Projection::default(),
             move_forward: false,
             move_back: false,
             move_right: false,
             move_left: false,
-            lmb_is_pressed: false,
-        }
+            move_up: false,
+            move_down: false,
+            move_fast: false,
+            move_slow: false,
+            roll_left: false,
+            roll_right: false,
+        };
+        // Same vectors as after any later rotation
+        camera.update_vectors();
+        camera
     }
 
+    // Moves the camera and returns the view matrix, for a variable timestep
     pub fn calculate_view_matrix(&mut self, frame_time_delta: Duration) -> Matrix4<f32> {
-        // Calculate vectors
-        let rot_around_y =
-            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.yaw.to_radians());
-        let rot_around_x =
-            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.pitch.to_radians());
-        let total_rot = rot_around_y * rot_around_x;
+        self.update(frame_time_delta);
+        self.view_matrix(1.0)
+    }
 
+    fn update_vectors(&mut self) {
+        let total_rot = self.orientation();
+
+        // Without roll, right stays in the XZ plane
         self.front = Vec3::normalize(&total_rot.transform_vector(&Vector3::z_axis()));
-        self.right = Vec3::normalize(&Vec3::cross(&Vec3::y_axis(), &self.front));
+        self.right = Vec3::normalize(&total_rot.transform_vector(&Vector3::x_axis()));
         self.up = Vec3::normalize(&Vec3::cross(&self.front, &self.right));
-
-        // Move
-        if self.move_forward {
-            self.position += self.front * self.move_speed * frame_time_delta.as_secs_f32();
-        }
-        if self.move_back {
-            self.position -= self.front * self.move_speed * frame_time_delta.as_secs_f32();
-        }
-        if self.move_right {
-            self.position += self.right * self.move_speed * frame_time_delta.as_secs_f32();
-        }
-        if self.move_left {
-            self.position -= self.right * self.move_speed * frame_time_delta.as_secs_f32();
-        }
-
-        let target = self.position + self.front;
-        Matrix4::look_at_lh(&self.position.into(), &target.into(), &self.up)
     }
 
     pub fn position(&self) -> [f32; 3] {
         self.position.into()
     }
+    // Teleports, without interpolating from the previous position
     pub fn set_position(&mut self, new_position: [f32; 3]) {
         self.position = new_position.into();
+        self.previous_position = self.position;
+        self.velocity = Vec3::zeros();
+    }
+    // Moves within one update, interpolated from the current position like regular movement
+    pub fn move_to(&mut self, new_position: [f32; 3]) {
+        self.previous_position = self.position;
+        self.position = new_position.into();
+        self.velocity = Vec3::zeros();
     }
     pub fn add_position(&mut self, add: [f32; 3]) {
         self.position += Vec3::from(add);
@@ -154,6 +251,7 @@ impl Camera {
     }
     pub fn set_yaw(&mut self, new_yaw: f32) {
         self.yaw = new_yaw % 360.0;
+        self.update_vectors();
     }
     pub fn add_yaw(&mut self, add: f32) {
         self.set_yaw(self.yaw + add);
@@ -164,36 +262,283 @@ impl Camera {
     }
     pub fn set_pitch(&mut self, new_pitch: f32) {
         self.pitch = new_pitch.clamp(-89.0, 89.0);
+        self.update_vectors();
     }
     pub fn add_pitch(&mut self, add: f32) {
         self.set_pitch(self.pitch + add);
     }
 
-    pub(crate) fn process_keyboard(&mut self, key: PhysicalKey, state: ElementState) {
-        if key == PhysicalKey::Code(KeyCode::KeyW) {
-            self.move_forward = state.is_pressed()
+    pub fn orientation(&self) -> UnitQuaternion<f32> {
+        orientation_from_angles(self.yaw, self.pitch, self.roll)
+    }
+    // Decomposed into yaw, pitch and roll, so the pitch limit and disabled roll still apply
+    pub fn set_orientation(&mut self, orientation: UnitQuaternion<f32>) {
+        let (yaw, pitch, roll) = angles_from_orientation(&orientation);
+        self.yaw = yaw;
+        self.pitch = pitch.clamp(-89.0, 89.0);
+        if self.roll_enabled {
+            self.roll = roll;
+        }
+        self.update_vectors();
+    }
+
+    pub fn roll(&self) -> f32 {
+        self.roll
+    }
+    // Ignored while roll is disabled
+    pub fn set_roll(&mut self, new_roll: f32) {
+        if self.roll_enabled {
+            self.roll = new_roll % 360.0;
+            self.update_vectors();
+        }
+    }
+    pub fn add_roll(&mut self, add: f32) {
+        self.set_roll(self.roll + add);
+    }
+
+    pub fn roll_enabled(&self) -> bool {
+        self.roll_enabled
+    }
+    // Disabling roll levels the camera again
+    pub fn set_roll_enabled(&mut self, roll_enabled: bool) {
+        self.roll_enabled = roll_enabled;
+        if !roll_enabled {
+            self.roll = 0.0;
+            self.update_vectors();
+        }
+    }
+    pub fn set_roll_speed(&mut self, roll_speed: f32) {
+        self.roll_speed = roll_speed;
+    }
+
+    pub fn move_speed(&self) -> f32 {
+        self.move_speed
+    }
+    pub fn set_move_speed(&mut self, move_speed: f32) {
+        self.move_speed = move_speed.clamp(MIN_MOVE_SPEED, MAX_MOVE_SPEED);
+    }
+    pub fn set_fast_multiplier(&mut self, fast_multiplier: f32) {
+        self.fast_multiplier = fast_multiplier;
+    }
+    pub fn set_slow_multiplier(&mut self, slow_multiplier: f32) {
+        self.slow_multiplier = slow_multiplier;
+    }
+
+    pub fn smoothing(&self) -> CameraSmoothing {
+        self.smoothing
+    }
+    pub fn set_smoothing(&mut self, smoothing: CameraSmoothing) {
+        self.smoothing = smoothing;
+    }
+
+    pub fn vertical_movement(&self) -> VerticalMovement {
+        self.vertical_movement
+    }
+    pub fn set_vertical_movement(&mut self, vertical_movement: VerticalMovement) {
+        self.vertical_movement = vertical_movement;
+    }
+}
+
+impl CameraTrait for Camera {
+    fn process_input(&mut self, input: &Input) {
+        self.move_forward = input.is_action_pressed(MOVE_FORWARD);
+        self.move_back = input.is_action_pressed(MOVE_BACK);
+        self.move_right = input.is_action_pressed(MOVE_RIGHT);
+        self.move_left = input.is_action_pressed(MOVE_LEFT);
+        self.move_up = input.is_action_pressed(MOVE_UP);
+        self.move_down = input.is_action_pressed(MOVE_DOWN);
+        self.move_fast = input.is_action_pressed(MOVE_FAST);
+        self.move_slow = input.is_action_pressed(MOVE_SLOW);
+        self.roll_left = input.is_action_pressed(ROLL_LEFT);
+        self.roll_right = input.is_action_pressed(ROLL_RIGHT);
+
+        if input.scroll_delta() != 0.0 {
+            self.set_move_speed(self.move_speed * SCROLL_SPEED_FACTOR.powf(input.scroll_delta()));
         }
-        if key == PhysicalKey::Code(KeyCode::KeyS) {
-            self.move_back = state.is_pressed();
+
+        if input.is_action_pressed(CAMERA_ROTATE) {
+            let (delta_x, delta_y) = input.mouse_delta();
+            self.pending_yaw += delta_x as f32 * self.sensitivity * self.screen_size_coefficient;
+            self.pending_pitch += delta_y as f32 * self.sensitivity * self.screen_size_coefficient;
         }
-        if key == PhysicalKey::Code(KeyCode::KeyD) {
-            self.move_right = state.is_pressed();
+
+        // Exponential filter, independent of the frame rate
+        let applied_fraction = if self.smoothing.mouse_smoothing > 0.0 {
+            1.0 - (-input.frame_time_delta().as_secs_f32() / self.smoothing.mouse_smoothing).exp()
+        } else {
+            1.0
+        };
+        let (yaw, pitch) = (
+            self.pending_yaw * applied_fraction,
+            self.pending_pitch * applied_fraction,
+        );
+        self.pending_yaw -= yaw;
+        self.pending_pitch -= pitch;
+        if yaw != 0.0 {
+            self.add_yaw(yaw);
         }
-        if key == PhysicalKey::Code(KeyCode::KeyA) {
-            self.move_left = state.is_pressed();
+        if pitch != 0.0 {
+            self.add_pitch(pitch);
         }
     }
 
-    pub(crate) fn process_mouse_motion(&mut self, delta_x: f64, delta_y: f64) {
-        if self.lmb_is_pressed {
-            self.add_yaw(delta_x as f32 * self.sensitivity * self.screen_size_coefficient);
-            self.add_pitch(delta_y as f32 * self.sensitivity * self.screen_size_coefficient);
+    fn update(&mut self, frame_time_delta: Duration) {
+        self.previous_position = self.position;
+
+        let vertical = match self.vertical_movement {
+            VerticalMovement::World => Vec3::y(),
+            VerticalMovement::Camera => self.up,
+        };
+        let mut direction = Vec3::zeros();
+        if self.move_forward {
+            direction += self.front;
+        }
+        if self.move_back {
+            direction -= self.front;
+        }
+        if self.move_right {
+            direction += self.right;
+        }
+        if self.move_left {
+            direction -= self.right;
+        }
+        if self.move_up {
+            direction += vertical;
+        }
+        if self.move_down {
+            direction -= vertical;
+        }
+
+        let mut move_speed = self.move_speed;
+        if self.move_fast {
+            move_speed *= self.fast_multiplier;
+        }
+        if self.move_slow {
+            move_speed *= self.slow_multiplier;
         }
+        let target_velocity = direction * move_speed;
+        let rate = if direction == Vec3::zeros() {
+            self.smoothing.damping
+        } else {
+            self.smoothing.acceleration
+        };
+        self.velocity = if rate > 0.0 {
+            let blend = 1.0 - (-rate * frame_time_delta.as_secs_f32()).exp();
+            self.velocity.lerp(&target_velocity, blend)
+        } else {
+            target_velocity
+        };
+        self.position += self.velocity * frame_time_delta.as_secs_f32();
+
+        if self.roll_enabled && self.roll_left != self.roll_right {
+            let roll_direction = if self.roll_right { 1.0 } else { -1.0 };
+            self.add_roll(roll_direction * self.roll_speed * frame_time_delta.as_secs_f32());
+        }
+    }
+
+    // View matrix at the position interpolated between the last two updates, see Frame::interpolation
+    // Rotation is not interpolated, it follows the mouse immediately
+    fn view_matrix(&self, interpolation: f32) -> Matrix4<f32> {
+        let position = self.previous_position.lerp(&self.position, interpolation);
+        let target = position + self.front;
+        Matrix4::look_at_lh(&position.into(), &target.into(), &self.up)
+    }
+
+    fn projection(&self) -> &Projection {
+        &self.projection
+    }
+    fn projection_mut(&mut self) -> &mut Projection {
+        &mut self.projection
     }
 
-    pub(crate) fn process_mouse_input(&mut self, button: MouseButton, state: ElementState) {
-        if button == MouseButton::Left {
-            self.lmb_is_pressed = state.is_pressed();
+    fn state(&self) -> CameraState {
+        CameraState {
+            position: self.position(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            roll: self.roll,
+            sensitivity: self.sensitivity,
+            move_speed: Some(self.move_speed),
+            orbit_distance: None,
+            projection: self.projection,
         }
     }
+    fn set_state(&mut self, state: &CameraState) {
+        self.set_position(state.position);
+        self.set_yaw(state.yaw);
+        self.set_pitch(state.pitch);
+        self.set_roll(state.roll);
+        self.sensitivity = state.sensitivity;
+        if let Some(move_speed) = state.move_speed {
+            self.set_move_speed(move_speed);
+        }
+        self.projection.set_shape(&state.projection);
+        self.pending_yaw = 0.0;
+        self.pending_pitch = 0.0;
+    }
+}
+
+// Rotation of the +Z front vector by yaw, then pitch, then roll, in degrees
+pub(crate) fn orientation_from_angles(yaw: f32, pitch: f32, roll: f32) -> UnitQuaternion<f32> {
+    let rot_around_y = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw.to_radians());
+    let rot_around_x = UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch.to_radians());
+    // Rolling right turns the right vector towards -Y
+    let rot_around_z = UnitQuaternion::from_axis_angle(&Vector3::z_axis(), -roll.to_radians());
+    rot_around_y * rot_around_x * rot_around_z
+}
+
+// Yaw, pitch and roll in degrees, inverse of orientation_from_angles
+pub(crate) fn angles_from_orientation(orientation: &UnitQuaternion<f32>) -> (f32, f32, f32) {
+    let front = orientation.transform_vector(&Vector3::z());
+    let right = orientation.transform_vector(&Vector3::x());
+    let yaw = front.x.atan2(front.z);
+    let pitch = (-front.y).clamp(-1.0, 1.0).asin();
+
+    // Right and up vectors without roll
+    let unrolled = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), yaw)
+        * UnitQuaternion::from_axis_angle(&Vector3::x_axis(), pitch);
+    let unrolled_right = unrolled.transform_vector(&Vector3::x());
+    let unrolled_up = unrolled.transform_vector(&Vector3::y());
+    let roll = (-right.dot(&unrolled_up)).atan2(right.dot(&unrolled_right));
+
+    (yaw.to_degrees(), pitch.to_degrees(), roll.to_degrees())
+}
+
+// Mouse sensitivity correction for the monitor size, shared by all cameras
+pub(crate) fn screen_size_coefficient(current_monitor: Option<MonitorHandle>) -> f32 {
+    if let Some(current_monitor) = current_monitor {
+        // Corrects standard screen size coefficient to current monitor
+        // On a large screen, the mouse moves a greater distance in pixels than on a small screen, for the same physical mouse offset.
+        // Example
+        // Small screen: 1 cm (mouse) = 20 pixels = 20 degrees (coefficient 1)
+        // Large screen: 1 cm (mouse) = 40 pixels = 20 degrees (coefficient 0.5)
+        // Example
+        // Standard monitor: 2560x1440
+        // Standard screen size coefficient: 0.5
+        // 1.
+        // Current monitor: 1920x1080
+        // Current to standard ratio: 0.75
+        // Screen coefficient: 0.5 * (2 - 0.75) = 0.625
+        // 2.
+        // Current monitor: 3200x1800
+        // Current to standard ratio: 1.25
+        // Screen coefficient: 0.5 * (2 - 1.25) = 0.375
+        // 3.
+        // Current monitor: 5120x2880
+        // Current to standard ratio: 2.0
+        // Screen coefficient: 0.25
+
+        // Calculate monitor size coefficient
+        let difference_x = current_monitor.size().width as f32 / STANDARD_SCREEN_SIZE.0 as f32;
+
+        let scale_factor = if difference_x < 2.0 {
+            2.0 - difference_x
+        } else {
+            1.0 / difference_x
+        };
+
+        STANDARD_SCREEN_SIZE_COEFFICIENT * scale_factor
+    } else {
+        STANDARD_SCREEN_SIZE_COEFFICIENT
+    }
 }
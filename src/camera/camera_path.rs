@@ -0,0 +1,264 @@
+// Scripted Camera flythroughs
+// Positions are interpolated with Catmull-Rom splines, orientations with slerp.
+// The spline tangents are scaled by the time between keyframes, so the velocity stays continuous
+// also with irregular keyframe intervals.
+// Driven from SampleTrait::update, so playback is repeatable with the fixed timestep,
+// also when recording a video with --record.
+//
+// Saved as text, one keyframe per line: <time in seconds> <x> <y> <z> <yaw> <pitch> <roll>
+
+use crate::camera::{Camera, CameraTrait, angles_from_orientation, orientation_from_angles};
+use anyhow::{Context, bail};
+use nalgebra::{UnitQuaternion, Vector3};
+use std::fmt::Write;
+use std::path::Path;
+use std::time::Duration;
+
+type Vec3 = Vector3<f32>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraKeyframe {
+    // Seconds, recorded paths start at 0, loaded ones may start later
+    pub time: f32,
+    pub position: [f32; 3],
+    pub orientation: UnitQuaternion<f32>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CameraPathMode {
+    // The camera moves with the user input
+    #[default]
+    Idle,
+    // Keyframes are taken from the live camera
+    Recording,
+    // The camera follows the keyframes
+    Playing,
+}
+
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    // Time between recorded keyframes
+    pub keyframe_interval: Duration,
+    // Restart playback at the end instead of returning to Idle
+    pub looping: bool,
+    mode: CameraPathMode,
+    elapsed: Duration,
+}
+
+impl Default for CameraPath {
+    fn default() -> Self {
+        Self {
+            keyframes: Vec::new(),
+            keyframe_interval: Duration::from_millis(500),
+            looping: false,
+            mode: CameraPathMode::Idle,
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl CameraPath {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read camera path from {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid camera path in {}", path.display()))
+    }
+
+    // Keyframes in the format of the module comment, with the default settings
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            keyframes: parse_keyframes(text)?,
+            ..Default::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut text = String::new();
+        for keyframe in &self.keyframes {
+            let [x, y, z] = keyframe.position;
+            let (yaw, pitch, roll) = angles_from_orientation(&keyframe.orientation);
+            writeln!(
+                text,
+                "{:?} {x:?} {y:?} {z:?} {yaw:?} {pitch:?} {roll:?}",
+                keyframe.time
+            )?;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, text)
+            .with_context(|| format!("Failed to write camera path to {}", path.display()))
+    }
+
+    pub fn mode(&self) -> CameraPathMode {
+        self.mode
+    }
+
+    // Seconds between the first and the last keyframe
+    pub fn duration(&self) -> f32 {
+        match (self.keyframes.first(), self.keyframes.last()) {
+            (Some(first), Some(last)) => last.time - first.time,
+            _ => 0.0,
+        }
+    }
+
+    // Replaces the keyframes, the first one is taken on the next update
+    pub fn start_recording(&mut self) {
+        self.keyframes.clear();
+        self.elapsed = Duration::ZERO;
+        self.mode = CameraPathMode::Recording;
+    }
+
+    // Ignored without at least two keyframes or if they all have the same time
+    pub fn start_playback(&mut self) {
+        if self.keyframes.len() < 2 || self.duration() <= 0.0 {
+            log::warn!("Camera path needs at least two keyframes at different times to play");
+            return;
+        }
+        self.elapsed = Duration::ZERO;
+        self.mode = CameraPathMode::Playing;
+    }
+
+    // Also ends a recording, with a last keyframe at the current camera
+    pub fn stop(&mut self, camera: &Camera) {
+        if self.mode == CameraPathMode::Recording && self.elapsed.as_secs_f32() > self.duration() {
+            self.record_keyframe(camera);
+        }
+        self.mode = CameraPathMode::Idle;
+    }
+
+    // Call from SampleTrait::update instead of Camera::update
+    // Playback starts at the first keyframe, also if its time is not zero
+    pub fn update(&mut self, camera: &mut Camera, update_timestep: Duration) {
+        match self.mode {
+            CameraPathMode::Idle => camera.update(update_timestep),
+            CameraPathMode::Recording => {
+                camera.update(update_timestep);
+                let next_keyframe_time = self.keyframe_interval * self.keyframes.len() as u32;
+                if self.elapsed >= next_keyframe_time {
+                    self.record_keyframe(camera);
+                }
+                self.elapsed += update_timestep;
+            }
+            CameraPathMode::Playing => {
+                self.elapsed += update_timestep;
+                let mut time = self.elapsed.as_secs_f32();
+                if time >= self.duration() {
+                    // A path without duration can not loop, e.g. if the keyframes changed during playback
+                    if self.looping && self.duration() > 0.0 {
+                        time %= self.duration();
+                        self.elapsed = Duration::from_secs_f32(time);
+                    } else {
+                        time = self.duration();
+                        self.mode = CameraPathMode::Idle;
+                    }
+                }
+                let start_time = self.keyframes.first().map_or(0.0, |keyframe| keyframe.time);
+                if let Some((position, orientation)) = self.sample(start_time + time) {
+                    camera.move_to(position);
+                    camera.set_orientation(orientation);
+                }
+            }
+        }
+    }
+
+    // Position and orientation at the time in seconds, clamped to the path
+    pub fn sample(&self, time: f32) -> Option<([f32; 3], UnitQuaternion<f32>)> {
+        let keyframes = &self.keyframes;
+        let (first, last) = (keyframes.first()?, keyframes.last()?);
+        if keyframes.len() == 1 || time <= first.time {
+            return Some((first.position, first.orientation));
+        }
+        if time >= last.time {
+            return Some((last.position, last.orientation));
+        }
+
+        // Segment between keyframes index and index + 1
+        let index = keyframes
+            .iter()
+            .rposition(|keyframe| keyframe.time <= time)
+            .unwrap_or(0);
+        let (start, end) = (&keyframes[index], &keyframes[index + 1]);
+        let segment_duration = (end.time - start.time).max(f32::EPSILON);
+        let t = (time - start.time) / segment_duration;
+
+        let position = hermite(
+            Vec3::from(start.position),
+            self.velocity(index) * segment_duration,
+            Vec3::from(end.position),
+            self.velocity(index + 1) * segment_duration,
+            t,
+        );
+        // Slerp as a fraction of the shortest rotation between the keyframes, always defined:
+        // orientations half a turn apart rotate around the axis of that rotation
+        let rotation = start.orientation.rotation_to(&end.orientation);
+        let orientation = rotation.powf(t) * start.orientation;
+        Some((position.into(), orientation))
+    }
+
+    // Spline velocity at a keyframe in units per second, from its neighbours (clamped at the ends)
+    // Zero between keyframes at the same time, e.g. at a cut
+    fn velocity(&self, index: usize) -> Vec3 {
+        let previous = &self.keyframes[index.saturating_sub(1)];
+        let next = &self.keyframes[(index + 1).min(self.keyframes.len() - 1)];
+        let duration = next.time - previous.time;
+        if duration > f32::EPSILON {
+            (Vec3::from(next.position) - Vec3::from(previous.position)) / duration
+        } else {
+            Vec3::zeros()
+        }
+    }
+
+    fn record_keyframe(&mut self, camera: &Camera) {
+        self.keyframes.push(CameraKeyframe {
+            time: self.elapsed.as_secs_f32(),
+            position: camera.position(),
+            orientation: camera.orientation(),
+        });
+    }
+}
+
+// Cubic Hermite spline through p1 (t = 0) and p2 (t = 1) with the tangents m1 and m2 per segment
+// With the tangents from CameraPath::velocity this is a non-uniform Catmull-Rom spline,
+// for equally spaced keyframes the same as the uniform one
+fn hermite(p1: Vec3, m1: Vec3, p2: Vec3, m2: Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    p1 * (2.0 * t3 - 3.0 * t2 + 1.0)
+        + m1 * (t3 - 2.0 * t2 + t)
+        + p2 * (3.0 * t2 - 2.0 * t3)
+        + m2 * (t3 - t2)
+}
+
+fn parse_keyframes(text: &str) -> anyhow::Result<Vec<CameraKeyframe>> {
+    let mut keyframes: Vec<CameraKeyframe> = Vec::new();
+    for (line_index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let values = line
+            .split_whitespace()
+            .map(str::parse::<f32>)
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Line {}: expected numbers", line_index + 1))?;
+        let [time, x, y, z, yaw, pitch, roll] = values[..] else {
+            bail!(
+                "Line {}: expected <time> <x> <y> <z> <yaw> <pitch> <roll>",
+                line_index + 1
+            );
+        };
+        if let Some(previous) = keyframes.last()
+            && time < previous.time
+        {
+            bail!("Line {}: keyframe times must increase", line_index + 1);
+        }
+        keyframes.push(CameraKeyframe {
+            time,
+            position: [x, y, z],
+            orientation: orientation_from_angles(yaw, pitch, roll),
+        });
+    }
+    Ok(keyframes)
+}
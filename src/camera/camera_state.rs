@@ -0,0 +1,170 @@
+// Camera viewpoint that can be saved to and loaded from a small TOML file, e.g.:
+//
+// position = [0.0, 1.5, -4.0]
+// yaw = 12.5
+// pitch = 20.0
+// roll = 0.0
+// sensitivity = 1.0
+// move_speed = 1.0
+// projection = "perspective"
+// fov_y = 45.0
+// z_near = 0.1
+// z_far = 100.0
+//
+// projection is "perspective" (fov_y, z_far), "infinite_perspective" (fov_y) or "orthographic" (height, z_far).
+// The aspect and reverse_z are not stored, SampleApp keeps them in sync with the render target.
+// Only the subset of TOML above is read: top level keys with numbers, strings and arrays of numbers,
+// comments and trailing commas in arrays are allowed.
+
+use crate::camera::projection::{Projection, ProjectionKind};
+use anyhow::{Context, bail};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraState {
+    pub position: [f32; 3],
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub sensitivity: f32,
+    // Only stored by cameras that have it
+    pub move_speed: Option<f32>,
+    pub orbit_distance: Option<f32>,
+    pub projection: Projection,
+}
+
+impl CameraState {
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        std::fs::write(path, self.to_text())
+            .with_context(|| format!("Failed to write camera state to {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read camera state from {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid camera state in {}", path.display()))
+    }
+
+    // Floats are written with {:?}, which always has a decimal point and reads back exactly
+    pub fn to_text(&self) -> String {
+        let [x, y, z] = self.position;
+        let mut text = format!(
+            "position = [{x:?}, {y:?}, {z:?}]\n\
+             yaw = {:?}\n\
+             pitch = {:?}\n\
+             roll = {:?}\n\
+             sensitivity = {:?}\n",
+            self.yaw, self.pitch, self.roll, self.sensitivity
+        );
+        if let Some(move_speed) = self.move_speed {
+            text += &format!("move_speed = {move_speed:?}\n");
+        }
+        if let Some(orbit_distance) = self.orbit_distance {
+            text += &format!("orbit_distance = {orbit_distance:?}\n");
+        }
+        text += &match self.projection.kind {
+            ProjectionKind::Perspective { fov_y, z_far } => {
+                format!("projection = \"perspective\"\nfov_y = {fov_y:?}\nz_far = {z_far:?}\n")
+            }
+            ProjectionKind::InfinitePerspective { fov_y } => {
+                format!("projection = \"infinite_perspective\"\nfov_y = {fov_y:?}\n")
+            }
+            ProjectionKind::Orthographic { height, z_far } => {
+                format!("projection = \"orthographic\"\nheight = {height:?}\nz_far = {z_far:?}\n")
+            }
+        };
+        text += &format!("z_near = {:?}\n", self.projection.z_near);
+        text
+    }
+
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut values = HashMap::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .with_context(|| format!("Line {}: expected <key> = <value>", line_index + 1))?;
+            values.insert(key.trim(), value.trim());
+        }
+
+        let value = |key: &str| {
+            values
+                .get(key)
+                .copied()
+                .with_context(|| format!("Missing {key}"))
+        };
+        let float = |key: &str| -> anyhow::Result<f32> {
+            value(key)?
+                .parse()
+                .with_context(|| format!("{key} is not a number"))
+        };
+        let optional_float = |key: &str| values.contains_key(key).then(|| float(key)).transpose();
+
+        let position = value("position")?
+            .strip_prefix('[')
+            .and_then(|position| position.strip_suffix(']'))
+            .context("position is not an array")?
+            .trim_end();
+        let position = position
+            .strip_suffix(',')
+            .unwrap_or(position)
+            .split(',')
+            .map(|component| component.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .context("position is not an array of numbers")?;
+        let Ok(position) = <[f32; 3]>::try_from(position) else {
+            bail!("position needs 3 components");
+        };
+
+        let kind = match value("projection")?.trim_matches('"') {
+            "perspective" => ProjectionKind::Perspective {
+                fov_y: float("fov_y")?,
+                z_far: float("z_far")?,
+            },
+            "infinite_perspective" => ProjectionKind::InfinitePerspective {
+                fov_y: float("fov_y")?,
+            },
+            "orthographic" => ProjectionKind::Orthographic {
+                height: float("height")?,
+                z_far: float("z_far")?,
+            },
+            projection => bail!("Unknown projection {projection}"),
+        };
+
+        Ok(Self {
+            position,
+            yaw: float("yaw")?,
+            pitch: float("pitch")?,
+            roll: optional_float("roll")?.unwrap_or(0.0),
+            sensitivity: float("sensitivity")?,
+            move_speed: optional_float("move_speed")?,
+            orbit_distance: optional_float("orbit_distance")?,
+            projection: Projection {
+                kind,
+                z_near: float("z_near")?,
+                ..Projection::default()
+            },
+        })
+    }
+}
+
+// Removes a # comment, unless the # is inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (index, character) in line.char_indices() {
+        match character {
+            '"' => in_string = !in_string,
+            '#' if !in_string => return &line[..index],
+            _ => {}
+        }
+    }
+    line
+}
@@ -0,0 +1,134 @@
+// View frustum in world space, for culling on the CPU
+// Planes are extracted from the view projection matrix (Gribb/Hartmann, with wgpu's [0, 1] depth range)
+
+use crate::camera::projection::{Projection, ProjectionKind};
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+type Vec3 = Vector3<f32>;
+
+// Points with normal.dot(point) + distance >= 0 are on the inner side
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    // Normalized, pointing into the frustum
+    pub normal: Vec3,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn from_point_normal(point: [f32; 3], normal: [f32; 3]) -> Self {
+        let normal = Vec3::from(normal).normalize();
+        Self {
+            normal,
+            distance: -normal.dot(&Vec3::from(point)),
+        }
+    }
+
+    // Normalizes the plane equation, a degenerate plane (infinite far plane) contains everything
+    fn from_coefficients(coefficients: Vector4<f32>) -> Self {
+        let normal = coefficients.xyz();
+        let length = normal.norm();
+        if length <= f32::EPSILON {
+            return Self {
+                normal: Vec3::zeros(),
+                distance: 1.0,
+            };
+        }
+        Self {
+            normal: normal / length,
+            distance: coefficients.w / length,
+        }
+    }
+
+    // Positive on the inner side
+    pub fn signed_distance(&self, point: &Vec3) -> f32 {
+        self.normal.dot(point) + self.distance
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    // Left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+    // Near corners, then far corners, each bottom left, bottom right, top right, top left
+    // None for InfinitePerspective, its far corners are at infinity
+    pub corners: Option<[Vec3; 8]>,
+}
+
+impl Frustum {
+    pub fn new(view_matrix: &Matrix4<f32>, projection: &Projection) -> Self {
+        let view_projection = projection.matrix() * view_matrix;
+        let row = |index: usize| view_projection.row(index).transpose();
+
+        // Clip space depth 0 <= z <= w, near and far swap with reverse Z
+        let (near, far) = if projection.reverse_z {
+            (row(3) - row(2), row(2))
+        } else {
+            (row(2), row(3) - row(2))
+        };
+        let planes = [
+            Plane::from_coefficients(row(3) + row(0)),
+            Plane::from_coefficients(row(3) - row(0)),
+            Plane::from_coefficients(row(3) + row(1)),
+            Plane::from_coefficients(row(3) - row(1)),
+            Plane::from_coefficients(near),
+            Plane::from_coefficients(far),
+        ];
+
+        // The far corners of an infinite projection are at infinity
+        let corners = match projection.kind {
+            ProjectionKind::InfinitePerspective { .. } => None,
+            _ => corners(&view_projection, projection.reverse_z),
+        };
+
+        Self { planes, corners }
+    }
+
+    pub fn contains_point(&self, point: [f32; 3]) -> bool {
+        let point = Vec3::from(point);
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(&point) >= 0.0)
+    }
+
+    // Conservative, spheres near the frustum edges outside of it can also pass
+    pub fn intersects_sphere(&self, center: [f32; 3], radius: f32) -> bool {
+        let center = Vec3::from(center);
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(&center) >= -radius)
+    }
+
+    // Conservative, boxes near the frustum edges outside of it can also pass
+    pub fn intersects_aabb(&self, min: [f32; 3], max: [f32; 3]) -> bool {
+        let (min, max) = (Vec3::from(min), Vec3::from(max));
+        self.planes.iter().all(|plane| {
+            // Corner furthest along the plane normal
+            let corner = Vec3::new(
+                if plane.normal.x >= 0.0 { max.x } else { min.x },
+                if plane.normal.y >= 0.0 { max.y } else { min.y },
+                if plane.normal.z >= 0.0 { max.z } else { min.z },
+            );
+            plane.signed_distance(&corner) >= 0.0
+        })
+    }
+}
+
+// Unprojects the corners of the clip space depth range
+fn corners(view_projection: &Matrix4<f32>, reverse_z: bool) -> Option<[Vec3; 8]> {
+    let inverse_view_projection = view_projection.try_inverse()?;
+    let (near_depth, far_depth) = if reverse_z { (1.0, 0.0) } else { (0.0, 1.0) };
+    let corner = |x: f32, y: f32, depth: f32| {
+        let corner = inverse_view_projection * Vector4::new(x, y, depth, 1.0);
+        corner.xyz() / corner.w
+    };
+    Some([
+        corner(-1.0, -1.0, near_depth),
+        corner(1.0, -1.0, near_depth),
+        corner(1.0, 1.0, near_depth),
+        corner(-1.0, 1.0, near_depth),
+        corner(-1.0, -1.0, far_depth),
+        corner(1.0, -1.0, far_depth),
+        corner(1.0, 1.0, far_depth),
+        corner(-1.0, 1.0, far_depth),
+    ])
+}
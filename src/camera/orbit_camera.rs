@@ -0,0 +1,180 @@
+// Camera orbiting around a target point, for model viewers
+// Left-handed coordinate system, same angles as Camera: yaw 0 and pitch 0 look along +Z
+
+use crate::camera::camera_state::CameraState;
+use crate::camera::projection::Projection;
+use crate::camera::{CameraTrait, screen_size_coefficient};
+use crate::input::Input;
+use crate::input::action_map::{CAMERA_PAN, CAMERA_ROTATE};
+use nalgebra::{Matrix4, UnitQuaternion, Vector3};
+use winit::monitor::MonitorHandle;
+
+// Distance change per mouse wheel line
+const SCROLL_ZOOM_FACTOR: f32 = 1.1;
+// Target movement per pixel at distance 1, scaled with the distance
+const PAN_SPEED: f32 = 0.002;
+
+type Vec3 = Vector3<f32>;
+
+pub struct OrbitCamera {
+    target: Vec3,
+    distance: f32,
+    min_distance: f32,
+    max_distance: f32,
+    // Yaw angle in degrees (along Y axis)
+    yaw: f32,
+    // Pitch angle in degrees (along X axis), positive looks down on the target
+    pitch: f32,
+    // Normalized vectors of the camera looking at the target
+    front: Vec3,
+    right: Vec3,
+    up: Vec3,
+    // Settings
+    sensitivity: f32,
+    // Screen size coefficient for sensitivity correction
+    screen_size_coefficient: f32,
+    projection: Projection,
+}
+
+impl OrbitCamera {
+    pub fn new(
+        target: [f32; 3],
+        distance: f32,
+        yaw: f32,
+        pitch: f32,
+        sensitivity: f32,
+        current_monitor: Option<MonitorHandle>,
+    ) -> Self {
+        let mut camera = Self {
+            target: target.into(),
+            distance,
+            min_distance: 0.01,
+            max_distance: 1000.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            front: Vec3::z(),
+            right: Vec3::x(),
+            up: Vec3::y(),
+            sensitivity,
+            screen_size_coefficient: screen_size_coefficient(current_monitor),
+            projection: Projection::default(),
+        };
+        camera.set_distance(distance);
+        camera.set_yaw(yaw);
+        camera.set_pitch(pitch);
+        camera
+    }
+
+    fn update_vectors(&mut self) {
+        let rot_around_y =
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), self.yaw.to_radians());
+        let rot_around_x =
+            UnitQuaternion::from_axis_angle(&Vector3::x_axis(), self.pitch.to_radians());
+        let total_rot = rot_around_y * rot_around_x;
+
+        self.front = Vec3::normalize(&total_rot.transform_vector(&Vector3::z_axis()));
+        self.right = Vec3::normalize(&total_rot.transform_vector(&Vector3::x_axis()));
+        self.up = Vec3::normalize(&Vec3::cross(&self.front, &self.right));
+    }
+
+    pub fn position(&self) -> [f32; 3] {
+        (self.target - self.front * self.distance).into()
+    }
+
+    pub fn target(&self) -> [f32; 3] {
+        self.target.into()
+    }
+    pub fn set_target(&mut self, new_target: [f32; 3]) {
+        self.target = new_target.into();
+    }
+
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+    pub fn set_distance(&mut self, new_distance: f32) {
+        self.distance = new_distance.clamp(self.min_distance, self.max_distance);
+    }
+    pub fn set_distance_limits(&mut self, min_distance: f32, max_distance: f32) {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance.max(min_distance);
+        self.set_distance(self.distance);
+    }
+
+    pub fn yaw(&self) -> f32 {
+        self.yaw
+    }
+    pub fn set_yaw(&mut self, new_yaw: f32) {
+        self.yaw = new_yaw % 360.0;
+        self.update_vectors();
+    }
+    pub fn add_yaw(&mut self, add: f32) {
+        self.set_yaw(self.yaw + add);
+    }
+
+    pub fn pitch(&self) -> f32 {
+        self.pitch
+    }
+    pub fn set_pitch(&mut self, new_pitch: f32) {
+        self.pitch = new_pitch.clamp(-89.0, 89.0);
+        self.update_vectors();
+    }
+    pub fn add_pitch(&mut self, add: f32) {
+        self.set_pitch(self.pitch + add);
+    }
+}
+
+impl CameraTrait for OrbitCamera {
+    // Dragging rotates the target with the mouse, scrolling zooms, panning moves the target
+    fn process_input(&mut self, input: &Input) {
+        let (delta_x, delta_y) = input.mouse_delta();
+        let (delta_x, delta_y) = (delta_x as f32, delta_y as f32);
+
+        if input.is_action_pressed(CAMERA_ROTATE) {
+            self.add_yaw(delta_x * self.sensitivity * self.screen_size_coefficient);
+            self.add_pitch(delta_y * self.sensitivity * self.screen_size_coefficient);
+        } else if input.is_action_pressed(CAMERA_PAN) {
+            // The target follows the mouse
+            let pan_speed = PAN_SPEED * self.sensitivity * self.distance;
+            self.target += (self.up * delta_y - self.right * delta_x) * pan_speed;
+        }
+
+        if input.scroll_delta() != 0.0 {
+            self.set_distance(self.distance * SCROLL_ZOOM_FACTOR.powf(-input.scroll_delta()));
+        }
+    }
+
+    // Follows the mouse immediately, nothing to interpolate
+    fn view_matrix(&self, _interpolation: f32) -> Matrix4<f32> {
+        let position = self.target - self.front * self.distance;
+        Matrix4::look_at_lh(&position.into(), &self.target.into(), &self.up)
+    }
+
+    fn projection(&self) -> &Projection {
+        &self.projection
+    }
+    fn projection_mut(&mut self) -> &mut Projection {
+        &mut self.projection
+    }
+
+    fn state(&self) -> CameraState {
+        CameraState {
+            position: self.position(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            roll: 0.0,
+            sensitivity: self.sensitivity,
+            move_speed: None,
+            orbit_distance: Some(self.distance),
+            projection: self.projection,
+        }
+    }
+    // Keeps the current distance for states without one, the target is in front of the position
+    fn set_state(&mut self, state: &CameraState) {
+        self.set_yaw(state.yaw);
+        self.set_pitch(state.pitch);
+        self.set_distance(state.orbit_distance.unwrap_or(self.distance));
+        self.target = Vector3::from(state.position) + self.front * self.distance;
+        self.sensitivity = state.sensitivity;
+        self.projection.set_shape(&state.projection);
+    }
+}
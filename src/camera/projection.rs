@@ -0,0 +1,126 @@
+// Projection matrices for wgpu
+// Left-handed view space looking along +Z, clip space depth in [0, 1] (OpenGL uses [-1, 1])
+
+use nalgebra::Matrix4;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectionKind {
+    // Vertical field of view in degrees
+    Perspective { fov_y: f32, z_far: f32 },
+    // No far plane, depth approaches the far value at infinity
+    InfinitePerspective { fov_y: f32 },
+    // Visible height in view space units, the width follows from the aspect
+    Orthographic { height: f32, z_far: f32 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub kind: ProjectionKind,
+    // Width / height, kept up to date by SampleApp for the camera of SampleTrait::process_camera_input
+    pub aspect: f32,
+    pub z_near: f32,
+    // Near plane at depth 1 and far plane at depth 0, for better precision with float depth formats
+    // Kept in sync with SampleRequirements::reverse_z by SampleApp, see GraphicsContext::depth_compare
+    pub reverse_z: bool,
+}
+
+impl Default for Projection {
+    fn default() -> Self {
+        Self::perspective(45.0, 1.0, 0.1, 100.0)
+    }
+}
+
+impl Projection {
+    pub fn perspective(fov_y: f32, aspect: f32, z_near: f32, z_far: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Perspective { fov_y, z_far },
+            aspect,
+            z_near,
+            reverse_z: false,
+        }
+    }
+
+    pub fn infinite_perspective(fov_y: f32, aspect: f32, z_near: f32) -> Self {
+        Self {
+            kind: ProjectionKind::InfinitePerspective { fov_y },
+            aspect,
+            z_near,
+            reverse_z: false,
+        }
+    }
+
+    pub fn orthographic(height: f32, aspect: f32, z_near: f32, z_far: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Orthographic { height, z_far },
+            aspect,
+            z_near,
+            reverse_z: false,
+        }
+    }
+
+    // Ignores a zero size, e.g. while the window is minimized
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        if width != 0 && height != 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    // Takes the kind and z_near of the projection, but keeps the aspect and reverse_z,
+    // which follow the render target, e.g. when restoring a CameraState
+    pub fn set_shape(&mut self, projection: &Projection) {
+        self.kind = projection.kind;
+        self.z_near = projection.z_near;
+    }
+
+    // Depth is z' / w', see the module comment for the conventions
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let near = self.z_near;
+        match self.kind {
+            ProjectionKind::Perspective { fov_y, z_far: far } => {
+                let (depth_scale, depth_offset) = if self.reverse_z {
+                    (near / (near - far), near * far / (far - near))
+                } else {
+                    (far / (far - near), near * far / (near - far))
+                };
+                self.perspective_matrix(fov_y, depth_scale, depth_offset)
+            }
+            ProjectionKind::InfinitePerspective { fov_y } => {
+                let (depth_scale, depth_offset) = if self.reverse_z {
+                    (0.0, near)
+                } else {
+                    (1.0, -near)
+                };
+                self.perspective_matrix(fov_y, depth_scale, depth_offset)
+            }
+            ProjectionKind::Orthographic { height, z_far: far } => {
+                let width = height * self.aspect;
+                let (depth_scale, depth_offset) = if self.reverse_z {
+                    (1.0 / (near - far), far / (far - near))
+                } else {
+                    (1.0 / (far - near), near / (near - far))
+                };
+                #[rustfmt::skip]
+                let matrix = Matrix4::new(
+                    2.0 / width, 0.0, 0.0, 0.0,
+                    0.0, 2.0 / height, 0.0, 0.0,
+                    0.0, 0.0, depth_scale, depth_offset,
+                    0.0, 0.0, 0.0, 1.0,
+                );
+                matrix
+            }
+        }
+    }
+
+    fn perspective_matrix(&self, fov_y: f32, depth_scale: f32, depth_offset: f32) -> Matrix4<f32> {
+        let focal_length = 1.0 / (fov_y.to_radians() / 2.0).tan();
+        // w' = z, the view space depth
+        #[rustfmt::skip]
+        let matrix = Matrix4::new(
+            focal_length / self.aspect, 0.0, 0.0, 0.0,
+            0.0, focal_length, 0.0, 0.0,
+            0.0, 0.0, depth_scale, depth_offset,
+            0.0, 0.0, 1.0, 0.0,
+        );
+        matrix
+    }
+}
@@ -0,0 +1,114 @@
+// World space rays for mouse picking, see CameraTrait::ray_from_cursor
+// Intersections return the distance along the normalized direction, hits behind the origin are ignored
+
+use crate::camera::frustum::Plane;
+use crate::camera::projection::Projection;
+use nalgebra::{Matrix4, Vector3, Vector4};
+
+type Vec3 = Vector3<f32>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Vec3,
+    // Normalized
+    pub direction: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: [f32; 3], direction: [f32; 3]) -> Self {
+        Self {
+            origin: origin.into(),
+            direction: Vec3::from(direction).normalize(),
+        }
+    }
+
+    // Ray through a pixel of the render target, starting on the near plane
+    // Cursor position in physical pixels from the top left corner, like WindowEvent::CursorMoved
+    pub fn from_screen(
+        cursor_position: (f64, f64),
+        target_size: (u32, u32),
+        view_matrix: &Matrix4<f32>,
+        projection: &Projection,
+    ) -> Option<Self> {
+        let (width, height) = target_size;
+        if width == 0 || height == 0 {
+            return None;
+        }
+        let x = (2.0 * cursor_position.0 / width as f64 - 1.0) as f32;
+        let y = (1.0 - 2.0 * cursor_position.1 / height as f64) as f32;
+
+        let inverse_view_projection = (projection.matrix() * view_matrix).try_inverse()?;
+        let unproject = |depth: f32| {
+            let point = inverse_view_projection * Vector4::new(x, y, depth, 1.0);
+            point.xyz() / point.w
+        };
+        // Depth 0.5 is finite also for infinite projections
+        let near_depth = if projection.reverse_z { 1.0 } else { 0.0 };
+        let origin = unproject(near_depth);
+        let direction = (unproject(0.5) - origin).try_normalize(f32::EPSILON)?;
+
+        Some(Self { origin, direction })
+    }
+
+    pub fn at(&self, distance: f32) -> Vec3 {
+        self.origin + self.direction * distance
+    }
+
+    // None if the ray is parallel to the plane
+    pub fn intersect_plane(&self, plane: &Plane) -> Option<f32> {
+        let denominator = plane.normal.dot(&self.direction);
+        if denominator.abs() <= f32::EPSILON {
+            return None;
+        }
+        let distance = -plane.signed_distance(&self.origin) / denominator;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    // Möller–Trumbore, hits both sides of the triangle
+    pub fn intersect_triangle(&self, a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> Option<f32> {
+        let (a, b, c) = (Vec3::from(a), Vec3::from(b), Vec3::from(c));
+        let edge_ab = b - a;
+        let edge_ac = c - a;
+        let p = self.direction.cross(&edge_ac);
+        let determinant = edge_ab.dot(&p);
+        if determinant.abs() <= f32::EPSILON {
+            return None;
+        }
+        let inverse_determinant = 1.0 / determinant;
+
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(&p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = to_origin.cross(&edge_ab);
+        let v = self.direction.dot(&q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let distance = edge_ac.dot(&q) * inverse_determinant;
+        (distance >= 0.0).then_some(distance)
+    }
+
+    // Slab test, 0 if the origin is inside the box
+    pub fn intersect_aabb(&self, min: [f32; 3], max: [f32; 3]) -> Option<f32> {
+        let mut entry = 0.0_f32;
+        let mut exit = f32::INFINITY;
+        for axis in 0..3 {
+            let inverse_direction = 1.0 / self.direction[axis];
+            let mut t0 = (min[axis] - self.origin[axis]) * inverse_direction;
+            let mut t1 = (max[axis] - self.origin[axis]) * inverse_direction;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            // NaN for a ray in the slab plane, ignored by max and min
+            entry = entry.max(t0);
+            exit = exit.min(t1);
+            if entry > exit {
+                return None;
+            }
+        }
+        Some(entry)
+    }
+}
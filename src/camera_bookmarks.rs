@@ -0,0 +1,58 @@
+// Camera viewpoints stored in camera_states/, so they survive restarts and can be handed to others
+// Ctrl + 1-9 saves the current viewpoint to a slot, 1-9 jumps back to it,
+// rebindable as the bookmark_save and bookmark_1 to bookmark_9 actions
+// Only enabled with SampleRequirements::camera_bookmarks, so other samples keep the digit keys
+// A saved file can be loaded on startup with --camera <PATH>
+
+use crate::camera::CameraTrait;
+use crate::camera::camera_state::CameraState;
+use crate::input::Input;
+use crate::input::action_map::{BOOKMARK_SAVE, BOOKMARK_SLOTS};
+use crate::log_error_chain;
+use std::path::PathBuf;
+
+const CAMERA_STATES_DIR: &str = "camera_states";
+
+pub(crate) struct CameraBookmarks {
+    // Lowercase sample name, files are named <prefix>_<slot>.toml
+    file_prefix: String,
+}
+
+impl CameraBookmarks {
+    pub fn new(sample_name: &str) -> Self {
+        Self {
+            file_prefix: sample_name.replace(' ', "_").to_lowercase(),
+        }
+    }
+
+    // Viewpoint saved on exit and restored on the next start, see SampleRequirements::restore_camera
+    pub fn last_viewpoint_path(&self) -> PathBuf {
+        PathBuf::from(CAMERA_STATES_DIR).join(format!("{}_last.toml", self.file_prefix))
+    }
+
+    fn slot_path(&self, slot: usize) -> PathBuf {
+        PathBuf::from(CAMERA_STATES_DIR).join(format!("{}_{slot}.toml", self.file_prefix))
+    }
+
+    pub fn process_input(&self, camera: &mut dyn CameraTrait, input: &Input) {
+        let Some(slot_index) = BOOKMARK_SLOTS
+            .iter()
+            .position(|slot| input.is_action_just_pressed(slot))
+        else {
+            return;
+        };
+        let slot_path = self.slot_path(slot_index + 1);
+
+        if input.is_action_pressed(BOOKMARK_SAVE) {
+            match camera.state().save(&slot_path) {
+                Ok(()) => log::info!("Camera bookmark saved to {}", slot_path.display()),
+                Err(err) => log_error_chain("Failed to save camera bookmark", &err),
+            }
+        } else if slot_path.exists() {
+            match CameraState::load(&slot_path) {
+                Ok(camera_state) => camera.set_state(&camera_state),
+                Err(err) => log_error_chain("Failed to load camera bookmark", &err),
+            }
+        }
+    }
+}
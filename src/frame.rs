@@ -0,0 +1,58 @@
+use std::time::Duration;
+use wgpu::{CommandEncoder, StoreOp, TextureView};
+
+// Everything SampleTrait::render needs for one frame
+// After render, SampleApp records tone mapping (if enabled) into the same command encoder,
+// submits it and then presents the frame (or captures it in headless mode)
+pub struct Frame {
+    // Render target in GraphicsContext::color_format, multisampled if sample_count is above 1
+    pub view: TextureView,
+    // Present if SampleRequirements::depth_format is set, with the same sample count as view
+    pub depth_view: Option<TextureView>,
+    // Present with MSAA, the last render pass writing to view has to resolve to it
+    pub resolve_target: Option<TextureView>,
+    // Views of the render target writing sRGB encoded or as is, regardless of SampleRequirements::color_output
+    // Render (or resolve with MSAA) to one of them instead of view to get the other encoding
+    // None if the target can not be viewed in that encoding, and always None when tone mapping
+    pub srgb_view: Option<TextureView>,
+    pub linear_view: Option<TextureView>,
+    pub sample_count: u32,
+    // Size of the render target in pixels
+    pub width: u32,
+    pub height: u32,
+    // Counts every rendered frame, also across device loss
+    pub index: u64,
+    // Sum of all frame time deltas, fixed when recording or in headless mode
+    pub elapsed: Duration,
+    pub frame_time_delta: Duration,
+    // Progress from the last SampleTrait::update towards the next one in [0, 1),
+    // for interpolating between the last two simulation states
+    pub interpolation: f32,
+    pub encoder: CommandEncoder,
+}
+
+impl Frame {
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height as f32
+    }
+
+    // Store operation for view, the multisampled texture is not needed after it was resolved
+    pub fn color_store_op(&self) -> StoreOp {
+        if self.resolve_target.is_some() {
+            StoreOp::Discard
+        } else {
+            StoreOp::Store
+        }
+    }
+}
+
+// Views of the surface texture or the offscreen render target a frame ends up in
+pub struct TargetViews {
+    // In the format returned by GraphicsContext::color_format (unless tone mapping),
+    // encoded as requested in SampleRequirements
+    pub view: TextureView,
+    // Writes are encoded to sRGB, None if the target format has no sRGB counterpart that can be used
+    pub srgb_view: Option<TextureView>,
+    // Writes are stored as is, None if the target format has no non-sRGB counterpart that can be used
+    pub linear_view: Option<TextureView>,
+}
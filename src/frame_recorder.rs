@@ -0,0 +1,115 @@
+// Records every presented frame with a fixed simulated timestep
+//
+// Output is either a directory of numbered PNG files (frame_000000.png, frame_000001.png, ...)
+// or, if the path ends with .y4m, a raw YUV4MPEG2 stream that can be fed directly to ffmpeg:
+// ffmpeg -i recording.y4m recording.mp4
+
+use crate::graphics_context::capture::CapturedImage;
+use anyhow::{Context, bail};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub struct FrameRecorder {
+    output_path: PathBuf,
+    fps: u32,
+    frame_count: u32,
+    // Y4M stream and frame size, created with the first frame
+    y4m: Option<(BufWriter<File>, u32, u32)>,
+}
+
+impl FrameRecorder {
+    pub fn new(output_path: impl Into<PathBuf>, fps: u32) -> anyhow::Result<Self> {
+        let output_path = output_path.into();
+        if fps == 0 {
+            bail!("Recording frame rate must not be zero");
+        }
+
+        let frame_recorder = Self {
+            output_path,
+            fps,
+            frame_count: 0,
+            y4m: None,
+        };
+        if !frame_recorder.is_y4m() {
+            std::fs::create_dir_all(&frame_recorder.output_path).with_context(|| {
+                format!("Failed to create {}", frame_recorder.output_path.display())
+            })?;
+        }
+
+        Ok(frame_recorder)
+    }
+
+    // Used instead of the measured frame time while recording
+    pub fn frame_time_delta(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.fps as f64)
+    }
+
+    pub fn frame_count(&self) -> u32 {
+        self.frame_count
+    }
+
+    pub fn record(&mut self, image: &CapturedImage) -> anyhow::Result<()> {
+        if self.is_y4m() {
+            self.write_y4m_frame(image)?;
+        } else {
+            let frame_path = self
+                .output_path
+                .join(format!("frame_{:06}.png", self.frame_count));
+            image.save_png(frame_path)?;
+        }
+        self.frame_count += 1;
+
+        Ok(())
+    }
+
+    fn is_y4m(&self) -> bool {
+        self.output_path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("y4m"))
+    }
+
+    fn write_y4m_frame(&mut self, image: &CapturedImage) -> anyhow::Result<()> {
+        if self.y4m.is_none() {
+            let file = File::create(&self.output_path)
+                .with_context(|| format!("Failed to create {}", self.output_path.display()))?;
+            let mut writer = BufWriter::new(file);
+            // 4:4:4 to avoid chroma subsampling, progressive, square pixels
+            writeln!(
+                writer,
+                "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+                image.width, image.height, self.fps
+            )?;
+            self.y4m = Some((writer, image.width, image.height));
+        }
+
+        let (writer, width, height) = self.y4m.as_mut().unwrap();
+        if (image.width, image.height) != (*width, *height) {
+            bail!(
+                "Frame size changed from {width}x{height} to {}x{} while recording Y4M",
+                image.width,
+                image.height
+            );
+        }
+
+        // Planar Y, Cb, Cr, BT.601 limited range
+        let pixel_count = (image.width * image.height) as usize;
+        let mut planes = vec![0u8; pixel_count * 3];
+        for (index, pixel) in image.rgba.chunks_exact(4).enumerate() {
+            let (r, g, b) = (pixel[0] as f32, pixel[1] as f32, pixel[2] as f32);
+            let y = 16.0 + (65.481 * r + 128.553 * g + 24.966 * b) / 255.0;
+            let cb = 128.0 + (-37.797 * r - 74.203 * g + 112.0 * b) / 255.0;
+            let cr = 128.0 + (112.0 * r - 93.786 * g - 18.214 * b) / 255.0;
+            planes[index] = y.round() as u8;
+            planes[pixel_count + index] = cb.round() as u8;
+            planes[pixel_count * 2 + index] = cr.round() as u8;
+        }
+
+        writer.write_all(b"FRAME\n")?;
+        writer.write_all(&planes)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
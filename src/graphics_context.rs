@@ -1,28 +1,53 @@
-mod surface_data;
+pub mod adapter_selection;
+pub mod capture;
+pub mod depth_data;
+mod device_errors;
+mod device_negotiation;
+pub mod multisample_data;
+pub mod offscreen_data;
+pub mod surface_data;
+pub mod tone_mapping;
 
 use crate::SampleRequirements;
+use crate::frame::{Frame, TargetViews};
+use crate::graphics_context::adapter_selection::AdapterSelection;
+use crate::graphics_context::capture::CapturedImage;
+use crate::graphics_context::depth_data::DepthData;
+use crate::graphics_context::device_errors::DeviceErrors;
+use crate::graphics_context::multisample_data::MultisampleData;
+use crate::graphics_context::offscreen_data::OffscreenData;
 use crate::graphics_context::surface_data::SurfaceData;
-use anyhow::Context;
+use crate::graphics_context::tone_mapping::{HDR_FORMAT, ToneMapping};
+use anyhow::{Context, bail};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use wgpu::{
-    Adapter, Backends, Device, DeviceDescriptor, Instance, InstanceDescriptor, PowerPreference,
-    Queue, RequestAdapterOptions, TextureUsages,
+    Adapter, CommandEncoderDescriptor, CompareFunction, DepthStencilState, Device,
+    DeviceDescriptor, Features, Instance, Limits, MultisampleState, Queue, Surface, SurfaceTexture,
+    TextureFormat, TextureUsages, TextureView,
 };
 use winit::dpi::{LogicalSize, PhysicalPosition, PhysicalSize};
 use winit::event_loop::ActiveEventLoop;
+use winit::monitor::MonitorHandle;
 use winit::window::Window;
 
 pub struct GraphicsContext {
-    pub window: Arc<Window>,
+    // None in headless mode
+    pub window: Option<Arc<Window>>,
     #[allow(unused)]
     instance: Instance,
     #[allow(unused)]
     adapter: Adapter,
     pub device: Arc<Device>,
     pub queue: Arc<Queue>,
-    pub surface_data: SurfaceData,
+    // Exactly one of surface_data and offscreen_data is present
+    pub surface_data: Option<SurfaceData>,
+    pub offscreen_data: Option<OffscreenData>,
+    // Present if SampleRequirements::hdr is set but the target is not HDR
+    pub tone_mapping: Option<ToneMapping>,
     pub last_frame_time: Instant,
+    device_errors: DeviceErrors,
+    reverse_z: bool,
 }
 
 impl GraphicsContext {
@@ -53,67 +78,415 @@ impl GraphicsContext {
                 .context("Failed to create window")?,
         );
 
+        Self::from_window(window, window_title, sample_requirements)
+    }
+
+    fn from_window(
+        window: Arc<Window>,
+        label: &str,
+        sample_requirements: &SampleRequirements,
+    ) -> anyhow::Result<Self> {
         // Instance
-        let instance = Instance::new(&InstanceDescriptor {
-            backends: Backends::PRIMARY,
-            ..Default::default()
-        });
+        let adapter_selection = AdapterSelection::new(sample_requirements);
+        let instance = adapter_selection.create_instance();
 
         // Surface
         let surface = instance
             .create_surface(window.clone())
             .context("Failed to create instance")?;
 
-        // Adapter
-        let adapter =
-            futures::executor::block_on(instance.request_adapter(&RequestAdapterOptions {
-                power_preference: PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            }))
-            .context("Failed to get adapter")?;
-
-        println!(
-            "Selected adapter: {}, {}",
-            adapter.get_info().name,
-            adapter.get_info().backend
+        // Adapter, Device and Queue
+        let (adapter, device, queue) = Self::request_device(
+            &adapter_selection,
+            &instance,
+            Some(&surface),
+            label,
+            sample_requirements,
+        )?;
+        let device_errors = DeviceErrors::new(
+            device.clone(),
+            label,
+            sample_requirements.validation_error_scopes,
         );
 
-        // Device and Queue
-        let (device, queue) = futures::executor::block_on(
-            adapter.request_device(
-                sample_requirements
-                    .device_descriptor
-                    .as_ref()
-                    .unwrap_or(&DeviceDescriptor::default()),
-            ),
-        )
-        .context("Failed to request device")?;
-        let device = Arc::new(device);
-        let queue = Arc::new(queue);
-
         let mut surface_data = SurfaceData::new(
             window.clone(),
             surface,
             &adapter,
             device.clone(),
             TextureUsages::RENDER_ATTACHMENT,
-        );
+            // Needed for capture_frame
+            TextureUsages::COPY_SRC,
+            sample_requirements,
+        )?;
         surface_data.configure(window.inner_size().width, window.inner_size().height);
 
+        let tone_mapping = if sample_requirements.hdr && !surface_data.is_hdr() {
+            log::info!("HDR surface format is not available, falling back to tone mapped SDR");
+            Some(ToneMapping::new(
+                device.clone(),
+                queue.clone(),
+                surface_data.surface_configuration.width,
+                surface_data.surface_configuration.height,
+                surface_data.surface_configuration.view_formats[0],
+            ))
+        } else {
+            None
+        };
+
         window.request_redraw();
         Ok(GraphicsContext {
-            window,
+            window: Some(window),
+            instance,
+            adapter,
+            device,
+            queue,
+            surface_data: Some(surface_data),
+            offscreen_data: None,
+            tone_mapping,
+            last_frame_time: Instant::now(),
+            device_errors,
+            reverse_z: sample_requirements.reverse_z,
+        })
+    }
+
+    // Creates a context without a window, rendering goes to an offscreen texture
+    pub fn new_headless(
+        label: &str,
+        width: u32,
+        height: u32,
+        sample_requirements: &SampleRequirements,
+    ) -> anyhow::Result<Self> {
+        // Instance
+        let adapter_selection = AdapterSelection::new(sample_requirements);
+        let instance = adapter_selection.create_instance();
+
+        // Adapter, Device and Queue
+        let (adapter, device, queue) = Self::request_device(
+            &adapter_selection,
+            &instance,
+            None,
+            label,
+            sample_requirements,
+        )?;
+        let device_errors = DeviceErrors::new(
+            device.clone(),
+            label,
+            sample_requirements.validation_error_scopes,
+        );
+
+        let offscreen_data = OffscreenData::new(
+            device.clone(),
+            &adapter,
+            width,
+            height,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::COPY_SRC,
+            sample_requirements,
+        )?;
+
+        // The offscreen target is always SDR, so it can be captured
+        let tone_mapping = sample_requirements.hdr.then(|| {
+            ToneMapping::new(
+                device.clone(),
+                queue.clone(),
+                width,
+                height,
+                offscreen_data.format,
+            )
+        });
+
+        Ok(GraphicsContext {
+            window: None,
             instance,
             adapter,
             device,
             queue,
-            surface_data,
+            surface_data: None,
+            offscreen_data: Some(offscreen_data),
+            tone_mapping,
             last_frame_time: Instant::now(),
+            device_errors,
+            reverse_z: sample_requirements.reverse_z,
         })
     }
 
+    fn request_device(
+        adapter_selection: &AdapterSelection,
+        instance: &Instance,
+        compatible_surface: Option<&Surface>,
+        label: &str,
+        sample_requirements: &SampleRequirements,
+    ) -> anyhow::Result<(Adapter, Arc<Device>, Arc<Queue>)> {
+        // Adapter
+        let adapter = adapter_selection.request_adapter(instance, compatible_surface)?;
+
+        println!(
+            "Selected adapter: {}, {}",
+            adapter.get_info().name,
+            adapter.get_info().backend
+        );
+
+        // Device and Queue
+        let (required_features, required_limits) =
+            device_negotiation::negotiate(&adapter, sample_requirements)?;
+        let (device, queue) =
+            futures::executor::block_on(adapter.request_device(&DeviceDescriptor {
+                label: Some(label),
+                required_features,
+                required_limits,
+                ..Default::default()
+            }))
+            .context("Failed to request device")?;
+
+        Ok((adapter, Arc::new(device), Arc::new(queue)))
+    }
+
+    // Creates a new context with the same window or offscreen size, used after the device was lost
+    // Everything created with the old device must be dropped before
+    pub fn recreate(
+        self,
+        label: &str,
+        sample_requirements: &SampleRequirements,
+    ) -> anyhow::Result<Self> {
+        let window = self.window.clone();
+        let (width, height) = self.target_size();
+        drop(self);
+
+        match window {
+            Some(window) => Self::from_window(window, label, sample_requirements),
+            None => Self::new_headless(label, width, height, sample_requirements),
+        }
+    }
+
+    // Set by the device lost callback, the context has to be recreated
+    pub fn is_device_lost(&self) -> bool {
+        self.device_errors.is_device_lost()
+    }
+
+    // Validation error scope around one frame, only if enabled in SampleRequirements
+    pub(crate) fn push_frame_error_scope(&self) {
+        self.device_errors.push_frame_scope();
+    }
+
+    pub(crate) fn pop_frame_error_scope(&self, frame_index: u64) {
+        self.device_errors.pop_frame_scope(frame_index);
+    }
+
+    // Prints all adapters available with the backends from SampleRequirements and the environment
+    pub fn list_adapters(sample_requirements: &SampleRequirements) {
+        adapter_selection::list_adapters(sample_requirements);
+    }
+
+    // Features granted to the device, required ones and the supported subset of optional ones
+    pub fn features(&self) -> Features {
+        self.device.features()
+    }
+
+    // Limits granted to the device
+    pub fn limits(&self) -> Limits {
+        self.device.limits()
+    }
+
+    // Whether the sample renders directly to an extended range surface
+    pub fn is_hdr_active(&self) -> bool {
+        self.surface_data
+            .as_ref()
+            .is_some_and(|surface_data| surface_data.is_hdr())
+    }
+
+    // Format of the views passed to SampleTrait::render
+    pub fn color_format(&self) -> TextureFormat {
+        if self.tone_mapping.is_some() {
+            HDR_FORMAT
+        } else if let Some(surface_data) = &self.surface_data {
+            surface_data.surface_configuration.view_formats[0]
+        } else {
+            self.offscreen_data.as_ref().unwrap().format
+        }
+    }
+
+    // Format of the depth texture, if requested in SampleRequirements
+    pub fn depth_format(&self) -> Option<TextureFormat> {
+        self.depth_data().map(|depth_data| depth_data.format)
+    }
+
+    // View of the depth texture matching the current render target
+    pub fn depth_view(&self) -> Option<TextureView> {
+        self.depth_data().map(DepthData::view)
+    }
+
+    // See SampleRequirements::reverse_z
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    // 0.0 with reverse Z, 1.0 otherwise
+    pub fn depth_clear_value(&self) -> f32 {
+        if self.reverse_z { 0.0 } else { 1.0 }
+    }
+
+    // Closer fragments pass, Greater with reverse Z, Less otherwise
+    pub fn depth_compare(&self) -> CompareFunction {
+        if self.reverse_z {
+            CompareFunction::Greater
+        } else {
+            CompareFunction::Less
+        }
+    }
+
+    // Depth stencil state for pipelines rendering with depth_view, None if there is no depth texture
+    pub fn depth_stencil_state(&self, depth_write_enabled: bool) -> Option<DepthStencilState> {
+        self.depth_format().map(|format| DepthStencilState {
+            format,
+            depth_write_enabled,
+            depth_compare: self.depth_compare(),
+            stencil: Default::default(),
+            bias: Default::default(),
+        })
+    }
+
+    fn depth_data(&self) -> Option<&DepthData> {
+        match &self.surface_data {
+            Some(surface_data) => surface_data.depth_data.as_ref(),
+            None => self.offscreen_data.as_ref().unwrap().depth_data.as_ref(),
+        }
+    }
+
+    // Sample count of the views passed to SampleTrait::render, see SampleRequirements::sample_count
+    pub fn sample_count(&self) -> u32 {
+        self.multisample_data()
+            .map_or(1, |multisample_data| multisample_data.sample_count)
+    }
+
+    // Multisample state for pipelines rendering to the views passed to SampleTrait::render
+    pub fn multisample_state(&self) -> MultisampleState {
+        MultisampleState {
+            count: self.sample_count(),
+            ..Default::default()
+        }
+    }
+
+    fn multisample_data(&self) -> Option<&MultisampleData> {
+        match &self.surface_data {
+            Some(surface_data) => surface_data.multisample_data.as_ref(),
+            None => self
+                .offscreen_data
+                .as_ref()
+                .unwrap()
+                .multisample_data
+                .as_ref(),
+        }
+    }
+
+    // Frame passed to SampleTrait::render, its view is the multisampled texture with MSAA,
+    // otherwise the HDR texture when tone mapping, otherwise the target view
+    // With MSAA the resolve target is the HDR texture when tone mapping, otherwise the target view
+    pub(crate) fn begin_frame(
+        &mut self,
+        target_views: &TargetViews,
+        index: u64,
+        elapsed: Duration,
+        frame_time_delta: Duration,
+        interpolation: f32,
+    ) -> Frame {
+        let (width, height) = self.target_size();
+        let hdr_view = self
+            .tone_mapping
+            .as_mut()
+            .map(|tone_mapping| tone_mapping.acquire(width, height));
+        // The sample does not render to the target when tone mapping
+        let (srgb_view, linear_view) = if hdr_view.is_some() {
+            (None, None)
+        } else {
+            (
+                target_views.srgb_view.clone(),
+                target_views.linear_view.clone(),
+            )
+        };
+        let output_view = hdr_view.unwrap_or_else(|| target_views.view.clone());
+        let (view, resolve_target) = match self.multisample_data() {
+            Some(multisample_data) => (multisample_data.view(), Some(output_view)),
+            None => (output_view, None),
+        };
+
+        Frame {
+            view,
+            depth_view: self.depth_view(),
+            resolve_target,
+            srgb_view,
+            linear_view,
+            sample_count: self.sample_count(),
+            width,
+            height,
+            index,
+            elapsed,
+            frame_time_delta,
+            interpolation,
+            encoder: self
+                .device
+                .create_command_encoder(&CommandEncoderDescriptor {
+                    label: Some("Frame"),
+                }),
+        }
+    }
+
+    // Tone maps the rendered frame to the target if needed, then submits it
+    pub(crate) fn end_frame(&self, mut frame: Frame, target_view: &TextureView) {
+        if let Some(tone_mapping) = &self.tone_mapping {
+            tone_mapping.apply(&mut frame.encoder, target_view);
+        }
+        self.queue.submit([frame.encoder.finish()]);
+    }
+
+    // Size of the render target in pixels
+    pub fn target_size(&self) -> (u32, u32) {
+        if let Some(surface_data) = &self.surface_data {
+            (
+                surface_data.surface_configuration.width,
+                surface_data.surface_configuration.height,
+            )
+        } else {
+            let offscreen_data = self.offscreen_data.as_ref().unwrap();
+            (offscreen_data.width(), offscreen_data.height())
+        }
+    }
+
+    // Reads back the surface texture acquired for the current frame, call before presenting it
+    // Fails if the surface does not support TextureUsages::COPY_SRC
+    pub fn capture_frame(&self, surface_texture: &SurfaceTexture) -> anyhow::Result<CapturedImage> {
+        let surface_data = self
+            .surface_data
+            .as_ref()
+            .context("Surface is not available in headless mode")?;
+        if !surface_data
+            .surface_configuration
+            .usage
+            .contains(TextureUsages::COPY_SRC)
+        {
+            bail!("Surface does not support copying from its textures");
+        }
+        CapturedImage::from_texture(&self.device, &self.queue, &surface_texture.texture)
+            .context("Failed to capture surface texture")
+    }
+
+    // Reads back the offscreen render target, only available in headless mode
+    pub fn capture_offscreen_target(&self) -> anyhow::Result<CapturedImage> {
+        let offscreen_data = self
+            .offscreen_data
+            .as_ref()
+            .context("Offscreen render target is only available in headless mode")?;
+        CapturedImage::from_texture(&self.device, &self.queue, &offscreen_data.texture)
+            .context("Failed to capture offscreen render target")
+    }
+
     pub fn window_aspect(&self) -> f32 {
-        self.window.inner_size().width as f32 / self.window.inner_size().height as f32
+        let (width, height) = self.target_size();
+        width as f32 / height as f32
+    }
+
+    pub fn current_monitor(&self) -> Option<MonitorHandle> {
+        self.window
+            .as_ref()
+            .and_then(|window| window.current_monitor())
     }
 }
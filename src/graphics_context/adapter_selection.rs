@@ -0,0 +1,115 @@
+// Adapter selection from SampleRequirements, overridable with environment variables:
+// WGPU_BACKEND                 Comma separated list of backends, e.g. "vulkan", "gl", "vulkan,gl"
+// WGPU_POWER_PREF              "low", "high" or "none"
+// WGPU_FORCE_FALLBACK_ADAPTER  "1" or "true" to use a software adapter
+// WGPU_ADAPTER_NAME            Case-insensitive substring of the adapter name
+
+use crate::SampleRequirements;
+use anyhow::{Context, bail};
+use wgpu::{
+    Adapter, Backends, DeviceType, Instance, InstanceDescriptor, PowerPreference,
+    RequestAdapterOptions, Surface,
+};
+
+pub struct AdapterSelection {
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    pub force_fallback_adapter: bool,
+    pub adapter_name: Option<String>,
+}
+
+impl AdapterSelection {
+    pub fn new(sample_requirements: &SampleRequirements) -> Self {
+        let force_fallback_adapter = match std::env::var("WGPU_FORCE_FALLBACK_ADAPTER") {
+            Ok(value) => value == "1" || value.eq_ignore_ascii_case("true"),
+            Err(_) => sample_requirements.force_fallback_adapter,
+        };
+
+        Self {
+            backends: sample_requirements.backends.with_env(),
+            power_preference: PowerPreference::from_env()
+                .unwrap_or(sample_requirements.power_preference),
+            force_fallback_adapter,
+            adapter_name: std::env::var("WGPU_ADAPTER_NAME")
+                .ok()
+                .or_else(|| sample_requirements.adapter_name.clone()),
+        }
+    }
+
+    pub fn create_instance(&self) -> Instance {
+        Instance::new(&InstanceDescriptor {
+            backends: self.backends,
+            ..Default::default()
+        })
+    }
+
+    pub fn request_adapter(
+        &self,
+        instance: &Instance,
+        compatible_surface: Option<&Surface>,
+    ) -> anyhow::Result<Adapter> {
+        let Some(adapter_name) = &self.adapter_name else {
+            return futures::executor::block_on(instance.request_adapter(&RequestAdapterOptions {
+                power_preference: self.power_preference,
+                force_fallback_adapter: self.force_fallback_adapter,
+                compatible_surface,
+            }))
+            .context("Failed to get adapter");
+        };
+
+        // The name filter is not supported by request_adapter, pick the first matching adapter
+        let adapters = instance.enumerate_adapters(self.backends);
+        let adapter_name = adapter_name.to_lowercase();
+        let adapter = adapters.iter().find(|adapter| {
+            let info = adapter.get_info();
+            info.name.to_lowercase().contains(&adapter_name)
+                && (!self.force_fallback_adapter || info.device_type == DeviceType::Cpu)
+                && compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+        });
+
+        match adapter {
+            Some(adapter) => Ok(adapter.clone()),
+            None => {
+                let available: Vec<String> = adapters
+                    .iter()
+                    .map(|adapter| {
+                        let info = adapter.get_info();
+                        format!("{} ({})", info.name, info.backend)
+                    })
+                    .collect();
+                bail!(
+                    "No suitable adapter matches the name \"{adapter_name}\", available adapters: [{}]",
+                    available.join(", ")
+                );
+            }
+        }
+    }
+}
+
+// Prints every adapter available with the selected backends along with its features and limits
+pub(crate) fn list_adapters(sample_requirements: &SampleRequirements) {
+    let adapter_selection = AdapterSelection::new(sample_requirements);
+    let instance = adapter_selection.create_instance();
+
+    let adapters = instance.enumerate_adapters(adapter_selection.backends);
+    if adapters.is_empty() {
+        println!(
+            "No adapters found for backends {:?}",
+            adapter_selection.backends
+        );
+        return;
+    }
+
+    for (index, adapter) in adapters.iter().enumerate() {
+        let info = adapter.get_info();
+        println!("Adapter {index}: {}", info.name);
+        println!("  Backend: {}", info.backend);
+        println!("  Device type: {:?}", info.device_type);
+        println!("  Driver: {} {}", info.driver, info.driver_info);
+        println!("  Features:");
+        for feature in adapter.features().iter_names() {
+            println!("    {}", feature.0);
+        }
+        println!("  Limits: {:#?}", adapter.limits());
+    }
+}
@@ -0,0 +1,246 @@
+use anyhow::{Context, bail};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::sync::mpsc;
+use wgpu::{
+    BufferDescriptor, BufferUsages, COPY_BYTES_PER_ROW_ALIGNMENT, CommandEncoderDescriptor, Device,
+    Extent3d, MapMode, Origin3d, PollType, Queue, TexelCopyBufferInfo, TexelCopyBufferLayout,
+    TexelCopyTextureInfo, Texture, TextureAspect, TextureFormat,
+};
+
+// Formats CapturedImage can read back, the 8-bit ones, Rgb10a2Unorm and the extended range Rgba16Float
+pub const CAPTURE_FORMATS: [TextureFormat; 6] = [
+    TextureFormat::Rgba8Unorm,
+    TextureFormat::Rgba8UnormSrgb,
+    TextureFormat::Bgra8Unorm,
+    TextureFormat::Bgra8UnormSrgb,
+    TextureFormat::Rgb10a2Unorm,
+    TextureFormat::Rgba16Float,
+];
+
+// Tightly packed 8-bit RGBA image, sRGB encoded
+pub struct CapturedImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl CapturedImage {
+    // Copies the texture into a mapped buffer and converts it to RGBA, see from_texels for the formats
+    // The texture must have been created with TextureUsages::COPY_SRC
+    pub fn from_texture(device: &Device, queue: &Queue, texture: &Texture) -> anyhow::Result<Self> {
+        let format = texture.format();
+        if !CAPTURE_FORMATS.contains(&format) {
+            bail!("Capturing textures of format {format:?} is not supported");
+        }
+        let bytes_per_pixel = match format {
+            TextureFormat::Rgba16Float => 8,
+            _ => 4,
+        };
+
+        let width = texture.width();
+        let height = texture.height();
+
+        // Rows in the buffer must be aligned to COPY_BYTES_PER_ROW_ALIGNMENT
+        let unpadded_bytes_per_row = width * bytes_per_pixel;
+        let padded_bytes_per_row = unpadded_bytes_per_row.div_ceil(COPY_BYTES_PER_ROW_ALIGNMENT)
+            * COPY_BYTES_PER_ROW_ALIGNMENT;
+
+        let buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Capture buffer"),
+            size: padded_bytes_per_row as u64 * height as u64,
+            usage: BufferUsages::COPY_DST | BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut command_encoder =
+            device.create_command_encoder(&CommandEncoderDescriptor::default());
+        command_encoder.copy_texture_to_buffer(
+            TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: Origin3d::ZERO,
+                aspect: TextureAspect::All,
+            },
+            TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        queue.submit([command_encoder.finish()]);
+
+        let (sender, receiver) = mpsc::channel();
+        buffer.map_async(MapMode::Read, .., move |result| {
+            let _ = sender.send(result);
+        });
+        device
+            .poll(PollType::Wait)
+            .context("Failed to wait for the capture copy")?;
+        receiver
+            .recv()
+            .context("Capture buffer mapping was cancelled")?
+            .context("Failed to map capture buffer")?;
+
+        let mut texels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+        {
+            let mapped_range = buffer.get_mapped_range(..);
+            for row in mapped_range.chunks_exact(padded_bytes_per_row as usize) {
+                texels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+            }
+        }
+        buffer.unmap();
+
+        Self::from_texels(format, width, height, &texels)
+    }
+
+    // Converts tightly packed texels of one of the CAPTURE_FORMATS to 8-bit sRGB encoded RGBA
+    // Both sRGB and non-sRGB 8-bit formats store the values that end up on screen
+    // (the GPU encodes on write to sRGB formats), so only the channel order needs converting
+    // Rgba16Float holds linear scRGB and is encoded to sRGB, values outside [0, 1] are clipped
+    // Rgb10a2Unorm is already encoded, like the non-sRGB 8-bit formats
+    pub fn from_texels(
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+        texels: &[u8],
+    ) -> anyhow::Result<Self> {
+        let pixel_count = (width * height) as usize;
+        let rgba: Vec<u8> = match format {
+            TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => texels.to_vec(),
+            TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => texels
+                .chunks_exact(4)
+                .flat_map(|bgra| [bgra[2], bgra[1], bgra[0], bgra[3]])
+                .collect(),
+            TextureFormat::Rgb10a2Unorm => texels
+                .chunks_exact(4)
+                .flat_map(|texel| {
+                    let bits = u32::from_le_bytes([texel[0], texel[1], texel[2], texel[3]]);
+                    let channel = |shift: u32| ((bits >> shift) & 0x3ff) as f32 / 1023.0;
+                    [
+                        unorm_to_u8(channel(0)),
+                        unorm_to_u8(channel(10)),
+                        unorm_to_u8(channel(20)),
+                        unorm_to_u8((bits >> 30) as f32 / 3.0),
+                    ]
+                })
+                .collect(),
+            TextureFormat::Rgba16Float => texels
+                .chunks_exact(8)
+                .flat_map(|texel| {
+                    let channel = |index: usize| {
+                        f16_to_f32(u16::from_le_bytes([texel[2 * index], texel[2 * index + 1]]))
+                    };
+                    [
+                        unorm_to_u8(linear_to_srgb(channel(0))),
+                        unorm_to_u8(linear_to_srgb(channel(1))),
+                        unorm_to_u8(linear_to_srgb(channel(2))),
+                        unorm_to_u8(channel(3)),
+                    ]
+                })
+                .collect(),
+            _ => bail!("Capturing textures of format {format:?} is not supported"),
+        };
+        if rgba.len() != pixel_count * 4 {
+            bail!(
+                "Expected {pixel_count} texels of format {format:?}, got {} bytes",
+                texels.len()
+            );
+        }
+
+        Ok(Self {
+            width,
+            height,
+            rgba,
+        })
+    }
+
+    pub fn load_png(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let file =
+            File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::ALPHA);
+        let mut reader = decoder
+            .read_info()
+            .with_context(|| format!("Failed to decode {}", path.display()))?;
+        let mut buffer = vec![0; reader.output_buffer_size().context("Image is too large")?];
+        let info = reader
+            .next_frame(&mut buffer)
+            .with_context(|| format!("Failed to decode {}", path.display()))?;
+
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            bail!(
+                "Unsupported PNG layout in {}: {:?} {:?}",
+                path.display(),
+                info.color_type,
+                info.bit_depth
+            );
+        }
+        buffer.truncate(info.buffer_size());
+
+        Ok(Self {
+            width: info.width,
+            height: info.height,
+            rgba: buffer,
+        })
+    }
+
+    pub fn save_png(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_source_srgb(png::SrgbRenderingIntent::Perceptual);
+        let mut writer = encoder
+            .write_header()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        writer
+            .write_image_data(&self.rgba)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        writer
+            .finish()
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        Ok(())
+    }
+}
+
+// Clips to [0, 1], NaN becomes 0
+fn unorm_to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// IEEE 754 half precision, infinities and NaN are passed on to be clipped by unorm_to_u8
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1f) as i32;
+    let mantissa = (bits & 0x3ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2.0_f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2.0_f32.powi(exponent - 15),
+    }
+}
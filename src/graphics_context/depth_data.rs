@@ -0,0 +1,74 @@
+use anyhow::bail;
+use std::sync::Arc;
+use wgpu::{
+    Device, Extent3d, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+    TextureView, TextureViewDescriptor,
+};
+
+// Depth(-stencil) texture of the same size as the render target, recreated with it
+pub struct DepthData {
+    device: Arc<Device>,
+    pub texture: Texture,
+    pub format: TextureFormat,
+    // Matches the color target, see SampleRequirements::sample_count
+    pub sample_count: u32,
+}
+
+impl DepthData {
+    pub fn new(
+        device: Arc<Device>,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        sample_count: u32,
+    ) -> anyhow::Result<Self> {
+        if !format.is_depth_stencil_format() {
+            bail!("{format:?} is not a depth format");
+        }
+        let missing_features = format.required_features() - device.features();
+        if !missing_features.is_empty() {
+            bail!("Depth format {format:?} requires features {missing_features:?}");
+        }
+
+        let texture = Self::create_texture(&device, width, height, format, sample_count);
+
+        Ok(Self {
+            device,
+            texture,
+            format,
+            sample_count,
+        })
+    }
+
+    pub fn configure(&mut self, width: u32, height: u32) {
+        self.texture =
+            Self::create_texture(&self.device, width, height, self.format, self.sample_count);
+    }
+
+    pub fn view(&self) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor::default())
+    }
+
+    fn create_texture(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        sample_count: u32,
+    ) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some("Depth texture"),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    }
+}
@@ -0,0 +1,67 @@
+// Routes wgpu errors to the log instead of panicking and tracks device loss
+//
+// Uncaptured errors are logged with the device label and, if RUST_BACKTRACE is set, a backtrace.
+// Validation errors can additionally be collected per frame with an error scope,
+// which attributes them to the frame that caused them.
+
+use std::backtrace::{Backtrace, BacktraceStatus};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use wgpu::{Device, DeviceLostReason, ErrorFilter};
+
+pub(crate) struct DeviceErrors {
+    device: Arc<Device>,
+    device_lost: Arc<AtomicBool>,
+    validation_error_scopes: bool,
+}
+
+impl DeviceErrors {
+    pub fn new(device: Arc<Device>, label: &str, validation_error_scopes: bool) -> Self {
+        let error_label = label.to_owned();
+        device.on_uncaptured_error(Box::new(move |error| {
+            let backtrace = Backtrace::capture();
+            if backtrace.status() == BacktraceStatus::Captured {
+                log::error!(
+                    "Uncaptured wgpu error on device \"{error_label}\": {error}\n{backtrace}"
+                );
+            } else {
+                log::error!("Uncaptured wgpu error on device \"{error_label}\": {error}");
+            }
+        }));
+
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let lost_label = label.to_owned();
+        let lost_flag = device_lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            // Destroyed is the expected reason when the device is dropped
+            if reason == DeviceLostReason::Unknown {
+                log::error!("Device \"{lost_label}\" lost: {message}");
+                lost_flag.store(true, Ordering::Release);
+            }
+        });
+
+        Self {
+            device,
+            device_lost,
+            validation_error_scopes,
+        }
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
+    pub fn push_frame_scope(&self) {
+        if self.validation_error_scopes {
+            self.device.push_error_scope(ErrorFilter::Validation);
+        }
+    }
+
+    pub fn pop_frame_scope(&self, frame_index: u64) {
+        if self.validation_error_scopes
+            && let Some(error) = futures::executor::block_on(self.device.pop_error_scope())
+        {
+            log::error!("Validation error in frame {frame_index}: {error}");
+        }
+    }
+}
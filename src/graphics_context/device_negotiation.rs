@@ -0,0 +1,125 @@
+// Intersects the features and limits requested in SampleRequirements with what the adapter supports
+//
+// Required features and minimum limits must be supported, otherwise a report listing
+// everything that is missing is returned as the error.
+// Optional features and preferred limits are granted as far as the adapter supports them.
+
+use crate::SampleRequirements;
+use anyhow::bail;
+use wgpu::{Adapter, Features, Limits};
+
+pub(crate) fn negotiate(
+    adapter: &Adapter,
+    sample_requirements: &SampleRequirements,
+) -> anyhow::Result<(Features, Limits)> {
+    let adapter_features = adapter.features();
+    let adapter_limits = adapter.limits();
+
+    let mut report = Vec::new();
+
+    let missing_features = sample_requirements.required_features - adapter_features;
+    for (feature_name, _) in missing_features.iter_names() {
+        report.push(format!("Missing feature {feature_name}"));
+    }
+
+    sample_requirements
+        .minimum_limits
+        .check_limits_with_fail_fn(&adapter_limits, false, |limit_name, required, supported| {
+            report.push(format!(
+                "Limit {limit_name}: required {required}, supported {supported}"
+            ));
+        });
+
+    if !report.is_empty() {
+        bail!(
+            "Adapter {} ({}) does not meet the sample requirements:\n{}",
+            adapter.get_info().name,
+            adapter.get_info().backend,
+            report.join("\n")
+        );
+    }
+
+    let optional_features = sample_requirements.optional_features;
+    let mut features =
+        sample_requirements.required_features | (optional_features & adapter_features);
+
+    // Sample counts other than 1 and 4 depend on the adapter, see supported_sample_count
+    if ![1, 4].contains(&sample_requirements.sample_count) {
+        features |= Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES & adapter_features;
+    }
+
+    let limits = match &sample_requirements.preferred_limits {
+        Some(preferred_limits) => negotiate_limits(
+            &sample_requirements.minimum_limits,
+            preferred_limits,
+            &adapter_limits,
+        ),
+        None => sample_requirements.minimum_limits.clone(),
+    };
+
+    for (feature_name, _) in (optional_features - features).iter_names() {
+        log::info!("Optional feature {feature_name} is not supported by the adapter");
+    }
+
+    Ok((features, limits))
+}
+
+// Each limit is the preferred value clamped to what the adapter supports,
+// but never worse than the minimum (which the adapter is known to support)
+fn negotiate_limits(minimum: &Limits, preferred: &Limits, adapter: &Limits) -> Limits {
+    let mut limits = minimum.clone();
+
+    // Higher is better
+    macro_rules! maximum {
+        ($($name:ident),* $(,)?) => {
+            $(limits.$name = preferred.$name.min(adapter.$name).max(minimum.$name);)*
+        };
+    }
+    // Lower is better
+    macro_rules! alignment {
+        ($($name:ident),* $(,)?) => {
+            $(limits.$name = preferred.$name.max(adapter.$name).min(minimum.$name);)*
+        };
+    }
+
+    maximum!(
+        max_texture_dimension_1d,
+        max_texture_dimension_2d,
+        max_texture_dimension_3d,
+        max_texture_array_layers,
+        max_bind_groups,
+        max_bindings_per_bind_group,
+        max_dynamic_uniform_buffers_per_pipeline_layout,
+        max_dynamic_storage_buffers_per_pipeline_layout,
+        max_sampled_textures_per_shader_stage,
+        max_samplers_per_shader_stage,
+        max_storage_buffers_per_shader_stage,
+        max_storage_textures_per_shader_stage,
+        max_uniform_buffers_per_shader_stage,
+        max_binding_array_elements_per_shader_stage,
+        max_binding_array_sampler_elements_per_shader_stage,
+        max_uniform_buffer_binding_size,
+        max_storage_buffer_binding_size,
+        max_vertex_buffers,
+        max_buffer_size,
+        max_vertex_attributes,
+        max_vertex_buffer_array_stride,
+        max_inter_stage_shader_components,
+        max_color_attachments,
+        max_color_attachment_bytes_per_sample,
+        max_compute_workgroup_storage_size,
+        max_compute_invocations_per_workgroup,
+        max_compute_workgroup_size_x,
+        max_compute_workgroup_size_y,
+        max_compute_workgroup_size_z,
+        max_compute_workgroups_per_dimension,
+        max_push_constant_size,
+        max_non_sampler_bindings,
+    );
+    alignment!(
+        min_uniform_buffer_offset_alignment,
+        min_storage_buffer_offset_alignment,
+    );
+
+    limits
+}
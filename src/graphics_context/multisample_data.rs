@@ -0,0 +1,104 @@
+use std::sync::Arc;
+use wgpu::{
+    Adapter, Device, Extent3d, Features, Texture, TextureDescriptor, TextureDimension,
+    TextureFormat, TextureFormatFeatures, TextureUsages, TextureView, TextureViewDescriptor,
+};
+
+// Multisampled color texture of the same size as the render target, recreated with it
+// The sample renders to it and resolves it to Frame::resolve_target
+pub struct MultisampleData {
+    device: Arc<Device>,
+    pub texture: Texture,
+    pub format: TextureFormat,
+    pub sample_count: u32,
+}
+
+impl MultisampleData {
+    pub fn new(
+        device: Arc<Device>,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let texture = Self::create_texture(&device, width, height, format, sample_count);
+
+        Self {
+            device,
+            texture,
+            format,
+            sample_count,
+        }
+    }
+
+    pub fn configure(&mut self, width: u32, height: u32) {
+        self.texture =
+            Self::create_texture(&self.device, width, height, self.format, self.sample_count);
+    }
+
+    pub fn view(&self) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor::default())
+    }
+
+    fn create_texture(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        sample_count: u32,
+    ) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some("Multisampled render target"),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        })
+    }
+}
+
+// Highest sample count not above the requested one that all formats support,
+// counts other than 1 and 4 need Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+pub(crate) fn supported_sample_count(
+    adapter: &Adapter,
+    device: &Device,
+    requested: u32,
+    formats: &[TextureFormat],
+) -> u32 {
+    let format_features = |format: TextureFormat| -> TextureFormatFeatures {
+        if device
+            .features()
+            .contains(Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            adapter.get_texture_format_features(format)
+        } else {
+            format.guaranteed_format_features(device.features())
+        }
+    };
+
+    let sample_count = [16, 8, 4, 2]
+        .into_iter()
+        .filter(|sample_count| *sample_count <= requested)
+        .find(|sample_count| {
+            formats.iter().all(|format| {
+                format_features(*format)
+                    .flags
+                    .sample_count_supported(*sample_count)
+            })
+        })
+        .unwrap_or(1);
+
+    if sample_count != requested.max(1) {
+        log::warn!(
+            "Sample count {requested} is not supported for {formats:?}, using {sample_count}"
+        );
+    }
+    sample_count
+}
@@ -0,0 +1,151 @@
+use crate::frame::TargetViews;
+use crate::graphics_context::depth_data::DepthData;
+use crate::graphics_context::multisample_data::{MultisampleData, supported_sample_count};
+use crate::graphics_context::tone_mapping::HDR_FORMAT;
+use crate::{ColorOutput, SampleRequirements};
+use std::sync::Arc;
+use wgpu::{
+    Adapter, Device, DownlevelFlags, Extent3d, Texture, TextureAspect, TextureDescriptor,
+    TextureDimension, TextureFormat, TextureUsages, TextureView, TextureViewDescriptor,
+    TextureViewDimension,
+};
+
+// Format of the offscreen render target for ColorOutput::Srgb
+const OFFSCREEN_FORMAT: TextureFormat = TextureFormat::Rgba8UnormSrgb;
+
+// Offscreen render target used instead of a surface in headless mode, its size never changes
+pub struct OffscreenData {
+    pub texture: Texture,
+    pub format: TextureFormat,
+    pub usage: TextureUsages,
+    // The sRGB or non-sRGB counterpart of format, if the texture can be viewed with it
+    counterpart_format: Option<TextureFormat>,
+    pub depth_data: Option<DepthData>,
+    pub multisample_data: Option<MultisampleData>,
+}
+
+impl OffscreenData {
+    pub fn new(
+        device: Arc<Device>,
+        adapter: &Adapter,
+        width: u32,
+        height: u32,
+        usage: TextureUsages,
+        sample_requirements: &SampleRequirements,
+    ) -> anyhow::Result<Self> {
+        let format = match sample_requirements.color_output {
+            ColorOutput::Srgb => OFFSCREEN_FORMAT,
+            ColorOutput::Linear => OFFSCREEN_FORMAT.remove_srgb_suffix(),
+        };
+        let counterpart_format = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::VIEW_FORMATS)
+            .then(|| match format.is_srgb() {
+                true => format.remove_srgb_suffix(),
+                false => format.add_srgb_suffix(),
+            });
+        let texture =
+            Self::create_texture(&device, width, height, format, usage, counterpart_format);
+
+        // The offscreen target is always SDR, HDR is tone mapped, see GraphicsContext::tone_mapping
+        let render_format = if sample_requirements.hdr {
+            HDR_FORMAT
+        } else {
+            format
+        };
+        let sample_count = supported_sample_count(
+            adapter,
+            &device,
+            sample_requirements.sample_count,
+            &[render_format]
+                .into_iter()
+                .chain(sample_requirements.depth_format)
+                .collect::<Vec<_>>(),
+        );
+        let multisample_data = (sample_count > 1).then(|| {
+            MultisampleData::new(device.clone(), width, height, render_format, sample_count)
+        });
+
+        let depth_data = sample_requirements
+            .depth_format
+            .map(|depth_format| {
+                DepthData::new(device.clone(), width, height, depth_format, sample_count)
+            })
+            .transpose()?;
+
+        Ok(Self {
+            texture,
+            format,
+            usage,
+            counterpart_format,
+            depth_data,
+            multisample_data,
+        })
+    }
+
+    pub fn acquire(&self) -> TargetViews {
+        let counterpart_view = |srgb: bool| {
+            self.counterpart_format
+                .filter(|format| format.is_srgb() == srgb)
+                .map(|format| self.create_view(format))
+        };
+        TargetViews {
+            view: self.create_view(self.format),
+            srgb_view: match self.format.is_srgb() {
+                true => Some(self.create_view(self.format)),
+                false => counterpart_view(true),
+            },
+            linear_view: match self.format.is_srgb() {
+                true => counterpart_view(false),
+                false => Some(self.create_view(self.format)),
+            },
+        }
+    }
+
+    fn create_view(&self, format: TextureFormat) -> TextureView {
+        self.texture.create_view(&TextureViewDescriptor {
+            label: None,
+            format: Some(format),
+            dimension: Some(TextureViewDimension::D2),
+            usage: Some(self.usage),
+            aspect: TextureAspect::All,
+            base_mip_level: 0,
+            mip_level_count: None,
+            base_array_layer: 0,
+            array_layer_count: None,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    fn create_texture(
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: TextureFormat,
+        usage: TextureUsages,
+        counterpart_format: Option<TextureFormat>,
+    ) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some("Offscreen render target"),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage,
+            view_formats: counterpart_format.as_slice(),
+        })
+    }
+}
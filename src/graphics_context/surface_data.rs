@@ -1,18 +1,33 @@
+use crate::frame::TargetViews;
+use crate::graphics_context::depth_data::DepthData;
+use crate::graphics_context::multisample_data::{MultisampleData, supported_sample_count};
+use crate::graphics_context::tone_mapping::HDR_FORMAT;
+use crate::{ColorOutput, SampleRequirements};
+use anyhow::{Context, bail};
+use std::fmt::{Display, Formatter};
 use std::sync::Arc;
 use wgpu::{
-    Adapter, CompositeAlphaMode, Device, PresentMode, Surface, SurfaceCapabilities,
-    SurfaceConfiguration, SurfaceError, SurfaceTexture, TextureAspect, TextureUsages, TextureView,
-    TextureViewDescriptor, TextureViewDimension,
+    Adapter, CompositeAlphaMode, Device, DownlevelFlags, PresentMode, Surface, SurfaceCapabilities,
+    SurfaceConfiguration, SurfaceError, SurfaceTexture, TextureAspect, TextureFormat,
+    TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
 };
 use winit::window::Window;
 
+// Extended range format holding linear extended sRGB (scRGB), only used if SampleRequirements::hdr is set
+// Rgb10a2Unorm is not used for HDR, wgpu can not select an HDR color space for it,
+// so it is shown in the SDR sRGB color space and the tone mapping fallback is used instead
+const HDR_SURFACE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
 pub struct SurfaceData {
     window: Arc<Window>,
     surface: Surface<'static>,
     device: Arc<Device>,
-    #[allow(unused)]
     capabilities: SurfaceCapabilities,
     pub surface_configuration: SurfaceConfiguration,
+    // Present if SampleRequirements::depth_format is set
+    pub depth_data: Option<DepthData>,
+    // Present if SampleRequirements::sample_count is above 1 and supported
+    pub multisample_data: Option<MultisampleData>,
     suboptimal: bool,
 }
 
@@ -23,16 +38,52 @@ impl SurfaceData {
         adapter: &Adapter,
         device: Arc<Device>,
         usage: TextureUsages,
-    ) -> Self {
+        optional_usage: TextureUsages,
+        sample_requirements: &SampleRequirements,
+    ) -> anyhow::Result<Self> {
+        if !adapter.is_surface_supported(&surface) {
+            bail!("Surface is not supported by the adapter");
+        }
         let capabilities = surface.get_capabilities(adapter);
-        assert!(adapter.is_surface_supported(&surface));
 
-        // [0] - preferred
-        let format = capabilities.formats[0];
+        // Optional usages are only requested if the surface supports them
+        let usage = usage | (optional_usage & capabilities.usages);
+
+        // Viewing the surface with its sRGB or non-sRGB counterpart format
+        let counterpart_views_supported = adapter
+            .get_downlevel_capabilities()
+            .flags
+            .contains(DownlevelFlags::SURFACE_VIEW_FORMATS);
+
+        let hdr_format = (sample_requirements.hdr
+            && capabilities.formats.contains(&HDR_SURFACE_FORMAT))
+        .then_some(HDR_SURFACE_FORMAT);
+        let format = match hdr_format {
+            Some(hdr_format) => hdr_format,
+            None => select_format(
+                &capabilities.formats,
+                sample_requirements.color_output,
+                counterpart_views_supported,
+            )
+            .context("Surface does not support any format")?,
+        };
+
+        let output_format = match sample_requirements.color_output {
+            ColorOutput::Srgb => format.add_srgb_suffix(),
+            ColorOutput::Linear => format.remove_srgb_suffix(),
+        };
+        let output_format = if output_format == format || counterpart_views_supported {
+            output_format
+        } else {
+            log::warn!(
+                "Surface format {format:?} can not be viewed as {output_format:?}, {:?} color output is not available",
+                sample_requirements.color_output
+            );
+            format
+        };
 
         let present_mode = 'present_mode: {
-            let preferences = [PresentMode::FifoRelaxed, PresentMode::Fifo];
-            for preferred_present_mode in preferences.iter() {
+            for preferred_present_mode in sample_requirements.present_modes.iter() {
                 if capabilities.present_modes.contains(preferred_present_mode) {
                     break 'present_mode *preferred_present_mode;
                 }
@@ -41,12 +92,20 @@ impl SurfaceData {
         };
 
         // Hint, will always be clamped to the supported range
-        let desired_maximum_frame_latency = 3;
+        let desired_maximum_frame_latency = sample_requirements.desired_maximum_frame_latency;
 
         let alpha_mode: CompositeAlphaMode = CompositeAlphaMode::Auto;
 
+        // [0] - format of the views passed to SampleTrait::render, followed by its counterpart
         // View formats of the same format as the texture are always allowed
-        let view_formats = vec![format];
+        let mut view_formats = vec![output_format];
+        if counterpart_views_supported {
+            for counterpart in [format.add_srgb_suffix(), format.remove_srgb_suffix()] {
+                if !view_formats.contains(&counterpart) {
+                    view_formats.push(counterpart);
+                }
+            }
+        }
 
         // SurfaceConfiguration
         let surface_configuration = SurfaceConfiguration {
@@ -60,25 +119,133 @@ impl SurfaceData {
             view_formats,
         };
 
-        Self {
+        // The sample renders to an HDR texture if the surface is not HDR, see GraphicsContext::tone_mapping
+        let render_format = if sample_requirements.hdr && format != HDR_SURFACE_FORMAT {
+            HDR_FORMAT
+        } else {
+            output_format
+        };
+        let sample_count = supported_sample_count(
+            adapter,
+            &device,
+            sample_requirements.sample_count,
+            &[render_format]
+                .into_iter()
+                .chain(sample_requirements.depth_format)
+                .collect::<Vec<_>>(),
+        );
+        let multisample_data = (sample_count > 1).then(|| {
+            MultisampleData::new(
+                device.clone(),
+                surface_configuration.width,
+                surface_configuration.height,
+                render_format,
+                sample_count,
+            )
+        });
+
+        let depth_data = sample_requirements
+            .depth_format
+            .map(|depth_format| {
+                DepthData::new(
+                    device.clone(),
+                    surface_configuration.width,
+                    surface_configuration.height,
+                    depth_format,
+                    sample_count,
+                )
+            })
+            .transpose()?;
+
+        Ok(Self {
             window,
             surface,
             device,
             capabilities,
             surface_configuration,
+            depth_data,
+            multisample_data,
             suboptimal: false,
-        }
+        })
     }
 
+    // A zero size (minimized window) keeps the previous configuration, acquire skips frames until resized
     pub fn configure(&mut self, width: u32, height: u32) {
-        self.surface_configuration.width = width.max(1);
-        self.surface_configuration.height = height.max(1);
+        if width == 0 || height == 0 {
+            return;
+        }
+        self.surface_configuration.width = width;
+        self.surface_configuration.height = height;
+
+        self.surface
+            .configure(&self.device, &self.surface_configuration);
+
+        if let Some(depth_data) = self.depth_data.as_mut() {
+            depth_data.configure(width, height);
+        }
+        if let Some(multisample_data) = self.multisample_data.as_mut() {
+            multisample_data.configure(width, height);
+        }
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        self.surface_configuration.present_mode
+    }
+
+    // Returns false if the surface does not support the present mode
+    pub fn set_present_mode(&mut self, present_mode: PresentMode) -> bool {
+        if !self.capabilities.present_modes.contains(&present_mode) {
+            return false;
+        }
+        self.surface_configuration.present_mode = present_mode;
+        self.surface
+            .configure(&self.device, &self.surface_configuration);
+        true
+    }
 
+    // Switches to the next supported mode of Immediate (no vsync), Mailbox and Fifo (vsync)
+    // and returns the mode in use afterwards
+    pub fn cycle_present_mode(&mut self) -> PresentMode {
+        const CYCLE: [PresentMode; 3] = [
+            PresentMode::Immediate,
+            PresentMode::Mailbox,
+            PresentMode::Fifo,
+        ];
+        let current = CYCLE
+            .iter()
+            .position(|present_mode| *present_mode == self.present_mode())
+            .unwrap_or(CYCLE.len() - 1);
+        for offset in 1..=CYCLE.len() {
+            if self.set_present_mode(CYCLE[(current + offset) % CYCLE.len()]) {
+                break;
+            }
+        }
+        self.present_mode()
+    }
+
+    pub fn set_desired_maximum_frame_latency(&mut self, desired_maximum_frame_latency: u32) {
+        self.surface_configuration.desired_maximum_frame_latency = desired_maximum_frame_latency;
         self.surface
             .configure(&self.device, &self.surface_configuration);
     }
 
-    pub fn acquire(&mut self) -> (SurfaceTexture, TextureView) {
+    // Whether the surface uses an extended range format
+    pub fn is_hdr(&self) -> bool {
+        self.surface_configuration.format == HDR_SURFACE_FORMAT
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        let size = self.window.inner_size();
+        size.width == 0 || size.height == 0
+    }
+
+    // Ok(None) means the frame should be skipped: the window is minimized, acquiring timed out
+    // or the surface is outdated even after reconfiguring (e.g. during display hot-plug)
+    pub fn acquire(&mut self) -> Result<Option<SurfaceFrame>, AcquireError> {
+        if self.is_minimized() {
+            return Ok(None);
+        }
+
         if self.suboptimal {
             self.configure(
                 self.window.inner_size().width,
@@ -87,32 +254,60 @@ impl SurfaceData {
         }
         self.suboptimal = false;
 
-        let surface_texture = self.surface.get_current_texture();
-        let surface_texture = match surface_texture {
+        let surface_texture = match self.surface.get_current_texture() {
             Ok(frame) => frame,
-            // If we timed out, just try again
-            Err(SurfaceError::Timeout) => self.surface
-                .get_current_texture()
-                .expect("Failed to acquire next surface texture"),
-            Err(
-                // If the surface is outdated, or was lost, reconfigure it.
-                SurfaceError::Outdated
-                | SurfaceError::Lost
-                | SurfaceError::Other
-                // If OutOfMemory happens, reconfiguring may not help, but we might as well try
-                | SurfaceError::OutOfMemory,
-            ) => {
-                self.configure(self.window.inner_size().width, self.window.inner_size().height);
-                self.surface
-                    .get_current_texture()
-                    .expect("Failed to acquire next surface texture")
+            Err(SurfaceError::Timeout) => return Ok(None),
+            Err(SurfaceError::OutOfMemory) => return Err(AcquireError::OutOfMemory),
+            // If the surface is outdated, or was lost, reconfigure it and try again
+            Err(SurfaceError::Outdated | SurfaceError::Lost | SurfaceError::Other) => {
+                self.configure(
+                    self.window.inner_size().width,
+                    self.window.inner_size().height,
+                );
+                match self.surface.get_current_texture() {
+                    Ok(frame) => frame,
+                    Err(SurfaceError::Timeout | SurfaceError::Outdated) => return Ok(None),
+                    Err(SurfaceError::OutOfMemory) => return Err(AcquireError::OutOfMemory),
+                    Err(error @ (SurfaceError::Lost | SurfaceError::Other)) => {
+                        return Err(AcquireError::SurfaceLost(error));
+                    }
+                }
             }
         };
         self.suboptimal = surface_texture.suboptimal;
 
-        let texture_view = surface_texture.texture.create_view(&TextureViewDescriptor {
+        let views = TargetViews {
+            view: self.create_view(&surface_texture, self.surface_configuration.view_formats[0]),
+            srgb_view: self.create_counterpart_view(&surface_texture, true),
+            linear_view: self.create_counterpart_view(&surface_texture, false),
+        };
+
+        Ok(Some(SurfaceFrame {
+            surface_texture,
+            views,
+        }))
+    }
+
+    // None if the surface can not be viewed with an sRGB (or linear) format
+    fn create_counterpart_view(
+        &self,
+        surface_texture: &SurfaceTexture,
+        srgb: bool,
+    ) -> Option<TextureView> {
+        let format = if srgb {
+            self.surface_configuration.format.add_srgb_suffix()
+        } else {
+            self.surface_configuration.format.remove_srgb_suffix()
+        };
+        let viewable = format == self.surface_configuration.format
+            || self.surface_configuration.view_formats.contains(&format);
+        (viewable && format.is_srgb() == srgb).then(|| self.create_view(surface_texture, format))
+    }
+
+    fn create_view(&self, surface_texture: &SurfaceTexture, format: TextureFormat) -> TextureView {
+        surface_texture.texture.create_view(&TextureViewDescriptor {
             label: None,
-            format: Some(self.surface_configuration.view_formats[0]),
+            format: Some(format),
             dimension: Some(TextureViewDimension::D2),
             usage: Some(self.surface_configuration.usage),
             aspect: TextureAspect::All,
@@ -120,8 +315,55 @@ impl SurfaceData {
             mip_level_count: None,
             base_array_layer: 0,
             array_layer_count: None,
-        });
+        })
+    }
+}
+
+// Prefers an 8 bit format with the requested encoding, then one that can be viewed with it,
+// then whatever the surface prefers
+fn select_format(
+    formats: &[TextureFormat],
+    color_output: ColorOutput,
+    counterpart_views_supported: bool,
+) -> Option<TextureFormat> {
+    let has_counterpart =
+        |format: &&TextureFormat| format.add_srgb_suffix() != format.remove_srgb_suffix();
+    let srgb = color_output == ColorOutput::Srgb;
+
+    let matching = formats
+        .iter()
+        .filter(has_counterpart)
+        .find(|format| format.is_srgb() == srgb);
+    let viewable = match counterpart_views_supported {
+        true => formats.iter().find(has_counterpart),
+        false => None,
+    };
+
+    matching.or(viewable).or(formats.first()).copied()
+}
+
+pub struct SurfaceFrame {
+    pub surface_texture: SurfaceTexture,
+    pub views: TargetViews,
+}
+
+// Errors after which rendering to the surface can not continue
+#[derive(Debug)]
+pub enum AcquireError {
+    // Reconfiguring the surface did not help
+    SurfaceLost(SurfaceError),
+    OutOfMemory,
+}
 
-        (surface_texture, texture_view)
+impl Display for AcquireError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AcquireError::SurfaceLost(error) => {
+                write!(f, "Surface lost and could not be reconfigured: {error}")
+            }
+            AcquireError::OutOfMemory => write!(f, "Out of memory while acquiring surface texture"),
+        }
     }
 }
+
+impl std::error::Error for AcquireError {}
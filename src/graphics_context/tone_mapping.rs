@@ -0,0 +1,319 @@
+// SDR fallback for SampleRequirements::hdr
+//
+// The sample renders to an Rgba16Float texture with unbounded linear colors,
+// which is then tone mapped to the surface (or offscreen target) with a fullscreen triangle.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+use wgpu::naga::ShaderStage;
+use wgpu::{
+    BindGroup, BindGroupDescriptor, BindGroupEntry, BindGroupLayout, BindGroupLayoutDescriptor,
+    BindGroupLayoutEntry, BindingResource, BindingType, Buffer, BufferBindingType,
+    BufferDescriptor, BufferUsages, Color, ColorTargetState, ColorWrites, CommandEncoder, Device,
+    Extent3d, FragmentState, LoadOp, Operations, PipelineLayoutDescriptor, Queue,
+    RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline, RenderPipelineDescriptor,
+    Sampler, SamplerBindingType, SamplerDescriptor, ShaderModuleDescriptor, ShaderSource,
+    ShaderStages, StoreOp, Texture, TextureDescriptor, TextureDimension, TextureFormat,
+    TextureSampleType, TextureUsages, TextureView, TextureViewDescriptor, TextureViewDimension,
+    VertexState,
+};
+
+// Format of the texture the sample renders to when tone mapping
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMappingOperator {
+    // Colors above 1.0 are clipped
+    Clamp,
+    Reinhard,
+    // Narkowicz's fit of the ACES filmic curve
+    #[default]
+    Aces,
+}
+
+pub struct ToneMapping {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    pub operator: ToneMappingOperator,
+    // Colors are multiplied by it before the operator is applied
+    pub exposure: f32,
+    // The tone mapped colors are encoded to sRGB in the shader if the target view is not sRGB
+    target_format: TextureFormat,
+    hdr_texture: Texture,
+    uniform_buffer: Buffer,
+    sampler: Sampler,
+    bind_group_layout: BindGroupLayout,
+    bind_group: BindGroup,
+    render_pipeline: RenderPipeline,
+}
+
+impl ToneMapping {
+    pub fn new(
+        device: Arc<Device>,
+        queue: Arc<Queue>,
+        width: u32,
+        height: u32,
+        target_format: TextureFormat,
+    ) -> Self {
+        let vertex_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Tone mapping vertex shader"),
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(
+                    r#"
+                    #version 460
+
+                    // Fullscreen triangle
+                    void main() {
+                        vec2 position = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+                        gl_Position = vec4(position * 2.0 - 1.0, 0.0, 1.0);
+                    }
+                "#,
+                ),
+                stage: ShaderStage::Vertex,
+                defines: Default::default(),
+            },
+        });
+
+        let fragment_shader = device.create_shader_module(ShaderModuleDescriptor {
+            label: Some("Tone mapping fragment shader"),
+            source: ShaderSource::Glsl {
+                shader: Cow::Borrowed(
+                    r#"
+                    #version 460
+
+                    layout(set = 0, binding = 0) uniform texture2D hdr_Texture;
+                    layout(set = 0, binding = 1) uniform sampler hdr_Sampler;
+                    layout(set = 0, binding = 2) uniform Parameters {
+                        float exposure;
+                        uint operator;
+                        uint encode_srgb;
+                    };
+
+                    out vec4 frag_Color;
+
+                    vec3 aces(vec3 x) {
+                        return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+                    }
+
+                    vec3 srgb_encode(vec3 x) {
+                        vec3 low = x * 12.92;
+                        vec3 high = 1.055 * pow(x, vec3(1.0 / 2.4)) - 0.055;
+                        return mix(high, low, vec3(lessThanEqual(x, vec3(0.0031308))));
+                    }
+
+                    void main() {
+                        vec4 hdr = texelFetch(sampler2D(hdr_Texture, hdr_Sampler), ivec2(gl_FragCoord.xy), 0);
+                        vec3 color = max(hdr.rgb * exposure, vec3(0.0));
+
+                        if (operator == 1) {
+                            color = color / (color + 1.0);
+                        } else if (operator == 2) {
+                            color = aces(color);
+                        }
+                        color = clamp(color, 0.0, 1.0);
+
+                        if (encode_srgb != 0) {
+                            color = srgb_encode(color);
+                        }
+                        frag_Color = vec4(color, 1.0);
+                    }
+                "#,
+                ),
+                stage: ShaderStage::Fragment,
+                defines: Default::default(),
+            },
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+            label: Some("Tone mapping"),
+            entries: &[
+                BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        sample_type: TextureSampleType::Float { filterable: false },
+                        view_dimension: TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::NonFiltering),
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Buffer {
+                        ty: BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&PipelineLayoutDescriptor {
+            label: Some("Tone mapping"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let render_pipeline = device.create_render_pipeline(&RenderPipelineDescriptor {
+            label: Some("Tone mapping"),
+            layout: Some(&pipeline_layout),
+            vertex: VertexState {
+                module: &vertex_shader,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(FragmentState {
+                module: &fragment_shader,
+                entry_point: Some("main"),
+                compilation_options: Default::default(),
+                targets: &[Some(ColorTargetState {
+                    format: target_format,
+                    blend: None,
+                    write_mask: ColorWrites::all(),
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        });
+
+        // exposure, operator, encode_srgb, padding
+        let uniform_buffer = device.create_buffer(&BufferDescriptor {
+            label: Some("Tone mapping parameters"),
+            size: 16,
+            usage: BufferUsages::UNIFORM | BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sampler = device.create_sampler(&SamplerDescriptor::default());
+
+        let hdr_texture = Self::create_texture(&device, width, height);
+        let bind_group = Self::create_bind_group(
+            &device,
+            &bind_group_layout,
+            &hdr_texture,
+            &sampler,
+            &uniform_buffer,
+        );
+
+        Self {
+            device,
+            queue,
+            operator: ToneMappingOperator::default(),
+            exposure: 1.0,
+            target_format,
+            hdr_texture,
+            uniform_buffer,
+            sampler,
+            bind_group_layout,
+            bind_group,
+            render_pipeline,
+        }
+    }
+
+    // View of the HDR texture for the sample to render to, recreated if the target size changed
+    pub fn acquire(&mut self, width: u32, height: u32) -> TextureView {
+        if self.hdr_texture.width() != width.max(1) || self.hdr_texture.height() != height.max(1) {
+            self.hdr_texture = Self::create_texture(&self.device, width, height);
+            self.bind_group = Self::create_bind_group(
+                &self.device,
+                &self.bind_group_layout,
+                &self.hdr_texture,
+                &self.sampler,
+                &self.uniform_buffer,
+            );
+        }
+        self.view()
+    }
+
+    pub fn view(&self) -> TextureView {
+        self.hdr_texture
+            .create_view(&TextureViewDescriptor::default())
+    }
+
+    // Tone maps the HDR texture to the target, call after the sample rendered the frame
+    pub fn apply(&self, command_encoder: &mut CommandEncoder, target_view: &TextureView) {
+        let mut parameters = Vec::with_capacity(16);
+        parameters.extend_from_slice(&self.exposure.to_ne_bytes());
+        parameters.extend_from_slice(&(self.operator as u32).to_ne_bytes());
+        parameters.extend_from_slice(&u32::from(!self.target_format.is_srgb()).to_ne_bytes());
+        parameters.extend_from_slice(&0u32.to_ne_bytes());
+        self.queue
+            .write_buffer(&self.uniform_buffer, 0, &parameters);
+
+        let mut render_pass = command_encoder.begin_render_pass(&RenderPassDescriptor {
+            label: Some("Tone mapping"),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+
+    fn create_texture(device: &Device, width: u32, height: u32) -> Texture {
+        device.create_texture(&TextureDescriptor {
+            label: Some("HDR render target"),
+            size: Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    }
+
+    fn create_bind_group(
+        device: &Device,
+        bind_group_layout: &BindGroupLayout,
+        hdr_texture: &Texture,
+        sampler: &Sampler,
+        uniform_buffer: &Buffer,
+    ) -> BindGroup {
+        device.create_bind_group(&BindGroupDescriptor {
+            label: Some("Tone mapping"),
+            layout: bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(
+                        &hdr_texture.create_view(&TextureViewDescriptor::default()),
+                    ),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(sampler),
+                },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: uniform_buffer.as_entire_binding(),
+                },
+            ],
+        })
+    }
+}
@@ -0,0 +1,184 @@
+// Keyboard and mouse state, updated from window and device events
+//
+// "Just pressed" and "just released" states, the mouse delta and the scroll delta cover one frame,
+// they are reset after SampleTrait::on_input was called.
+
+pub mod action_map;
+
+use crate::input::action_map::{ActionMap, Binding};
+use std::collections::HashSet;
+use std::time::Duration;
+use winit::event::{DeviceEvent, ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+// Pixels per line for touchpads reporting the scroll delta in pixels
+const PIXELS_PER_SCROLL_LINE: f64 = 20.0;
+
+#[derive(Default)]
+pub struct Input {
+    keys_pressed: HashSet<KeyCode>,
+    keys_just_pressed: HashSet<KeyCode>,
+    keys_just_released: HashSet<KeyCode>,
+    mouse_buttons_pressed: HashSet<MouseButton>,
+    mouse_buttons_just_pressed: HashSet<MouseButton>,
+    mouse_buttons_just_released: HashSet<MouseButton>,
+    frame_time_delta: Duration,
+    mouse_delta: (f64, f64),
+    cursor_position: Option<(f64, f64)>,
+    // In lines, positive is away from the user
+    scroll_delta: f32,
+    pub action_map: ActionMap,
+}
+
+impl Input {
+    pub fn new(action_map: ActionMap) -> Self {
+        Self {
+            action_map,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_key_pressed(&self, key: KeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+    pub fn is_key_just_pressed(&self, key: KeyCode) -> bool {
+        self.keys_just_pressed.contains(&key)
+    }
+    pub fn is_key_just_released(&self, key: KeyCode) -> bool {
+        self.keys_just_released.contains(&key)
+    }
+
+    pub fn is_mouse_button_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_pressed.contains(&button)
+    }
+    pub fn is_mouse_button_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_just_pressed.contains(&button)
+    }
+    pub fn is_mouse_button_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons_just_released.contains(&button)
+    }
+
+    // Time since the previous frame, for frame rate independent smoothing of the input
+    pub fn frame_time_delta(&self) -> Duration {
+        self.frame_time_delta
+    }
+
+    // Raw mouse motion during this frame, only while the cursor is in the window
+    pub fn mouse_delta(&self) -> (f64, f64) {
+        self.mouse_delta
+    }
+
+    // In physical pixels from the top left corner of the window, None while outside of it
+    pub fn cursor_position(&self) -> Option<(f64, f64)> {
+        self.cursor_position
+    }
+
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+
+    // Any binding of the action is pressed
+    pub fn is_action_pressed(&self, action: &str) -> bool {
+        self.action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(key) => self.is_key_pressed(key),
+                Binding::MouseButton(button) => self.is_mouse_button_pressed(button),
+            })
+    }
+    pub fn is_action_just_pressed(&self, action: &str) -> bool {
+        self.action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(key) => self.is_key_just_pressed(key),
+                Binding::MouseButton(button) => self.is_mouse_button_just_pressed(button),
+            })
+    }
+    pub fn is_action_just_released(&self, action: &str) -> bool {
+        self.action_map
+            .bindings(action)
+            .iter()
+            .any(|binding| match *binding {
+                Binding::Key(key) => self.is_key_just_released(key),
+                Binding::MouseButton(button) => self.is_mouse_button_just_released(button),
+            })
+    }
+
+    pub(crate) fn process_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => {
+                            if self.keys_pressed.insert(key) {
+                                self.keys_just_pressed.insert(key);
+                            }
+                        }
+                        ElementState::Released => {
+                            if self.keys_pressed.remove(&key) {
+                                self.keys_just_released.insert(key);
+                            }
+                        }
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed => {
+                    if self.mouse_buttons_pressed.insert(*button) {
+                        self.mouse_buttons_just_pressed.insert(*button);
+                    }
+                }
+                ElementState::Released => {
+                    if self.mouse_buttons_pressed.remove(button) {
+                        self.mouse_buttons_just_released.insert(*button);
+                    }
+                }
+            },
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll_delta += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        (position.y / PIXELS_PER_SCROLL_LINE) as f32
+                    }
+                };
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor_position = Some((position.x, position.y));
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor_position = None,
+            // Keys held while the window loses focus would otherwise stay pressed
+            WindowEvent::Focused(false) => {
+                self.keys_just_released.extend(self.keys_pressed.drain());
+                self.mouse_buttons_just_released
+                    .extend(self.mouse_buttons_pressed.drain());
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn process_device_event(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion {
+            delta: (delta_x, delta_y),
+        } = event
+        {
+            self.mouse_delta.0 += delta_x;
+            self.mouse_delta.1 += delta_y;
+        }
+    }
+
+    pub(crate) fn begin_frame(&mut self, frame_time_delta: Duration) {
+        self.frame_time_delta = frame_time_delta;
+    }
+
+    // Resets the per frame state
+    pub(crate) fn end_frame(&mut self) {
+        self.keys_just_pressed.clear();
+        self.keys_just_released.clear();
+        self.mouse_buttons_just_pressed.clear();
+        self.mouse_buttons_just_released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll_delta = 0.0;
+    }
+}
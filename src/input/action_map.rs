@@ -0,0 +1,276 @@
+// Named actions bound to keys and mouse buttons
+//
+// Bindings can be loaded from a text file, one action per line, e.g.:
+//
+// # ESDF instead of WASD
+// move_forward = KeyE, ArrowUp
+// move_left = KeyS, ArrowLeft
+// move_back = KeyD, ArrowDown
+// move_right = KeyF, ArrowRight
+// camera_rotate = MouseRight
+//
+// Keys are named like winit::keyboard::KeyCode (physical key positions on a US layout),
+// mouse buttons are MouseLeft, MouseRight, MouseMiddle, MouseBack and MouseForward.
+// Actions in the file replace the default bindings of the same action, the others are kept.
+// Actions that have no default binding are added, samples can use their own action names.
+
+use anyhow::{Context, bail};
+use std::collections::HashMap;
+use std::path::Path;
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+// Actions used by the cameras
+pub const MOVE_FORWARD: &str = "move_forward";
+pub const MOVE_BACK: &str = "move_back";
+pub const MOVE_RIGHT: &str = "move_right";
+pub const MOVE_LEFT: &str = "move_left";
+pub const MOVE_UP: &str = "move_up";
+pub const MOVE_DOWN: &str = "move_down";
+pub const MOVE_FAST: &str = "move_fast";
+pub const MOVE_SLOW: &str = "move_slow";
+pub const ROLL_LEFT: &str = "roll_left";
+pub const ROLL_RIGHT: &str = "roll_right";
+pub const CAMERA_ROTATE: &str = "camera_rotate";
+// Used by OrbitCamera
+pub const CAMERA_PAN: &str = "camera_pan";
+// Camera bookmarks, a slot is saved if bookmark_save is held and loaded otherwise,
+// only used with SampleRequirements::camera_bookmarks
+pub const BOOKMARK_SAVE: &str = "bookmark_save";
+pub const BOOKMARK_SLOTS: [&str; 9] = [
+    "bookmark_1",
+    "bookmark_2",
+    "bookmark_3",
+    "bookmark_4",
+    "bookmark_5",
+    "bookmark_6",
+    "bookmark_7",
+    "bookmark_8",
+    "bookmark_9",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    Key(KeyCode),
+    MouseButton(MouseButton),
+}
+
+pub struct ActionMap {
+    actions: HashMap<String, Vec<Binding>>,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let mut action_map = Self::empty();
+        action_map.bind(MOVE_FORWARD, Binding::Key(KeyCode::KeyW));
+        action_map.bind(MOVE_FORWARD, Binding::Key(KeyCode::ArrowUp));
+        action_map.bind(MOVE_BACK, Binding::Key(KeyCode::KeyS));
+        action_map.bind(MOVE_BACK, Binding::Key(KeyCode::ArrowDown));
+        action_map.bind(MOVE_RIGHT, Binding::Key(KeyCode::KeyD));
+        action_map.bind(MOVE_RIGHT, Binding::Key(KeyCode::ArrowRight));
+        action_map.bind(MOVE_LEFT, Binding::Key(KeyCode::KeyA));
+        action_map.bind(MOVE_LEFT, Binding::Key(KeyCode::ArrowLeft));
+        action_map.bind(MOVE_UP, Binding::Key(KeyCode::KeyE));
+        action_map.bind(MOVE_UP, Binding::Key(KeyCode::Space));
+        action_map.bind(MOVE_DOWN, Binding::Key(KeyCode::KeyQ));
+        action_map.bind(MOVE_FAST, Binding::Key(KeyCode::ShiftLeft));
+        action_map.bind(MOVE_SLOW, Binding::Key(KeyCode::AltLeft));
+        action_map.bind(ROLL_LEFT, Binding::Key(KeyCode::KeyZ));
+        action_map.bind(ROLL_RIGHT, Binding::Key(KeyCode::KeyC));
+        action_map.bind(CAMERA_ROTATE, Binding::MouseButton(MouseButton::Left));
+        action_map.bind(CAMERA_PAN, Binding::MouseButton(MouseButton::Middle));
+        action_map.bind(CAMERA_PAN, Binding::MouseButton(MouseButton::Right));
+        action_map.bind(BOOKMARK_SAVE, Binding::Key(KeyCode::ControlLeft));
+        action_map.bind(BOOKMARK_SAVE, Binding::Key(KeyCode::ControlRight));
+        let slot_keys = [
+            KeyCode::Digit1,
+            KeyCode::Digit2,
+            KeyCode::Digit3,
+            KeyCode::Digit4,
+            KeyCode::Digit5,
+            KeyCode::Digit6,
+            KeyCode::Digit7,
+            KeyCode::Digit8,
+            KeyCode::Digit9,
+        ];
+        for (slot, key) in BOOKMARK_SLOTS.into_iter().zip(slot_keys) {
+            action_map.bind(slot, Binding::Key(key));
+        }
+        action_map
+    }
+}
+
+impl ActionMap {
+    pub fn empty() -> Self {
+        Self {
+            actions: HashMap::new(),
+        }
+    }
+
+    // Default bindings overridden by the ones in the file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bindings from {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Invalid bindings in {}", path.display()))
+    }
+
+    // Default bindings overridden by the ones in the text, see the module comment for the format
+    pub fn parse(text: &str) -> anyhow::Result<Self> {
+        let mut action_map = Self::default();
+        action_map.apply(text)?;
+        Ok(action_map)
+    }
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn unbind_all(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    fn apply(&mut self, text: &str) -> anyhow::Result<()> {
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (action, bindings) = line.split_once('=').with_context(|| {
+                format!(
+                    "Line {}: expected <action> = <binding>, ...",
+                    line_index + 1
+                )
+            })?;
+            let action = action.trim();
+            self.unbind_all(action);
+            for name in bindings
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+            {
+                match parse_binding(name) {
+                    Some(binding) => self.bind(action, binding),
+                    None => bail!("Line {}: unknown key or button {name}", line_index + 1),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_binding(name: &str) -> Option<Binding> {
+    let mouse_button = match name {
+        "MouseLeft" => Some(MouseButton::Left),
+        "MouseRight" => Some(MouseButton::Right),
+        "MouseMiddle" => Some(MouseButton::Middle),
+        "MouseBack" => Some(MouseButton::Back),
+        "MouseForward" => Some(MouseButton::Forward),
+        _ => None,
+    };
+    if let Some(mouse_button) = mouse_button {
+        return Some(Binding::MouseButton(mouse_button));
+    }
+
+    BINDABLE_KEYS
+        .iter()
+        .find(|key| format!("{key:?}") == name)
+        .map(|key| Binding::Key(*key))
+}
+
+// Keys that can be named in a bindings file
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::KeyA,
+    KeyCode::KeyB,
+    KeyCode::KeyC,
+    KeyCode::KeyD,
+    KeyCode::KeyE,
+    KeyCode::KeyF,
+    KeyCode::KeyG,
+    KeyCode::KeyH,
+    KeyCode::KeyI,
+    KeyCode::KeyJ,
+    KeyCode::KeyK,
+    KeyCode::KeyL,
+    KeyCode::KeyM,
+    KeyCode::KeyN,
+    KeyCode::KeyO,
+    KeyCode::KeyP,
+    KeyCode::KeyQ,
+    KeyCode::KeyR,
+    KeyCode::KeyS,
+    KeyCode::KeyT,
+    KeyCode::KeyU,
+    KeyCode::KeyV,
+    KeyCode::KeyW,
+    KeyCode::KeyX,
+    KeyCode::KeyY,
+    KeyCode::KeyZ,
+    KeyCode::Digit0,
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::ArrowUp,
+    KeyCode::ArrowDown,
+    KeyCode::ArrowLeft,
+    KeyCode::ArrowRight,
+    KeyCode::Space,
+    KeyCode::Tab,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Backspace,
+    KeyCode::ShiftLeft,
+    KeyCode::ShiftRight,
+    KeyCode::ControlLeft,
+    KeyCode::ControlRight,
+    KeyCode::AltLeft,
+    KeyCode::AltRight,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Semicolon,
+    KeyCode::Quote,
+    KeyCode::Slash,
+    KeyCode::Backslash,
+    KeyCode::BracketLeft,
+    KeyCode::BracketRight,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Backquote,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+];
@@ -1,46 +1,286 @@
 pub mod camera;
+mod camera_bookmarks;
+pub mod frame;
+pub mod frame_recorder;
 pub mod graphics_context;
+pub mod input;
+mod sample_args;
 
-use crate::camera::Camera;
+use crate::camera::CameraTrait;
+use crate::camera::camera_state::CameraState;
+use crate::camera_bookmarks::CameraBookmarks;
+use crate::frame::{Frame, TargetViews};
+use crate::frame_recorder::FrameRecorder;
 use crate::graphics_context::GraphicsContext;
-use std::time::{Duration, Instant};
-use wgpu::{DeviceDescriptor, TextureView};
+use crate::input::Input;
+use crate::input::action_map::ActionMap;
+use crate::sample_args::SampleArgs;
+use anyhow::Context;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use wgpu::{Backends, Features, Limits, PollType, PowerPreference, PresentMode, TextureFormat};
 use winit::application::ApplicationHandler;
-use winit::event::{DeviceEvent, DeviceId, WindowEvent};
+use winit::event::{DeviceEvent, DeviceId, ElementState, WindowEvent};
 use winit::event_loop::{ActiveEventLoop, ControlFlow, DeviceEvents, EventLoop};
+use winit::keyboard::{KeyCode, PhysicalKey};
 use winit::window::WindowId;
 
 pub struct SampleApp<S: SampleTrait + Sized> {
     sample_name: &'static str,
     sample_requirements: SampleRequirements,
-    event_loop: Option<EventLoop<()>>,
     graphics_context: Option<GraphicsContext>,
     sample_context: Option<S>,
     mouse_in_window: bool,
+    capture_requested: bool,
+    frame_recorder: Option<FrameRecorder>,
+    frame_index: u64,
+    elapsed: Duration,
+    // Simulated time not yet consumed by SampleTrait::update
+    update_accumulator: Duration,
+    input: Input,
+    camera_bookmarks: CameraBookmarks,
+    // Applied to the camera before the next frame, the first one or the one after a device loss
+    pending_camera_state: Option<CameraState>,
 }
 
 impl<S: SampleTrait + Sized> SampleApp<S> {
     pub fn new(sample_name: &'static str, sample_requirements: SampleRequirements) -> Self {
-        let event_loop = EventLoop::new().expect("Failed to create event loop");
-        event_loop.set_control_flow(ControlFlow::Poll);
-        event_loop.listen_device_events(DeviceEvents::WhenFocused);
-
         Self {
             sample_name,
             sample_requirements,
-            event_loop: Some(event_loop),
             graphics_context: None,
             sample_context: None,
             mouse_in_window: false,
+            capture_requested: false,
+            frame_recorder: None,
+            frame_index: 0,
+            elapsed: Duration::ZERO,
+            update_accumulator: Duration::ZERO,
+            input: Input::default(),
+            camera_bookmarks: CameraBookmarks::new(sample_name),
+            pending_camera_state: None,
         }
     }
 
     pub fn run(&mut self) {
-        self.event_loop
+        let sample_args = match SampleArgs::parse() {
+            Ok(sample_args) => sample_args,
+            Err(err) => {
+                log_error_chain("Failed to parse arguments", &err);
+                std::process::exit(1);
+            }
+        };
+
+        if sample_args.list_adapters {
+            GraphicsContext::list_adapters(&self.sample_requirements);
+            return;
+        }
+
+        if let Some(bindings_path) = &sample_args.bindings_path {
+            match ActionMap::load(bindings_path) {
+                Ok(action_map) => self.input.action_map = action_map,
+                Err(err) => {
+                    log_error_chain("Failed to load bindings", &err);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if let Some(camera_path) = &sample_args.camera_path {
+            match CameraState::load(camera_path) {
+                Ok(camera_state) => self.pending_camera_state = Some(camera_state),
+                Err(err) => {
+                    log_error_chain("Failed to load camera state", &err);
+                    std::process::exit(1);
+                }
+            }
+        } else if self.sample_requirements.restore_camera && sample_args.headless_settings.is_none()
+        {
+            let last_viewpoint_path = self.camera_bookmarks.last_viewpoint_path();
+            if last_viewpoint_path.exists() {
+                match CameraState::load(&last_viewpoint_path) {
+                    Ok(camera_state) => self.pending_camera_state = Some(camera_state),
+                    Err(err) => log_error_chain("Failed to restore the last viewpoint", &err),
+                }
+            }
+        }
+
+        if let Some(record_path) = &sample_args.record_path {
+            match FrameRecorder::new(record_path, sample_args.record_fps) {
+                Ok(frame_recorder) => self.frame_recorder = Some(frame_recorder),
+                Err(err) => {
+                    log_error_chain("Failed to start recording", &err);
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        if let Some(headless_settings) = sample_args.headless_settings {
+            if let Err(err) = self.run_headless(&headless_settings) {
+                log_error_chain("Failed to run headless sample", &err);
+                std::process::exit(1);
+            }
+            return;
+        }
+
+        let event_loop = EventLoop::new().expect("Failed to create event loop");
+        event_loop.set_control_flow(ControlFlow::Poll);
+        event_loop.listen_device_events(DeviceEvents::WhenFocused);
+
+        event_loop.run_app(self).expect("Failed to run sample app");
+    }
+
+    // Renders a fixed number of frames to an offscreen texture without creating a window
+    pub fn run_headless(&mut self, headless_settings: &HeadlessSettings) -> anyhow::Result<()> {
+        let graphics_context = GraphicsContext::new_headless(
+            self.sample_name,
+            headless_settings.width,
+            headless_settings.height,
+            &self.sample_requirements,
+        )
+        .context("Failed to create graphics context")?;
+        let sample_context =
+            S::new(&graphics_context).context("Failed to create sample context")?;
+
+        self.graphics_context = Some(graphics_context);
+        self.sample_context = Some(sample_context);
+
+        // The recording frame rate takes precedence over the headless frame time delta
+        let frame_time_delta = self
+            .frame_recorder
+            .as_ref()
+            .map_or(headless_settings.frame_time_delta, |frame_recorder| {
+                frame_recorder.frame_time_delta()
+            });
+
+        for _ in 0..headless_settings.frame_count {
+            if self.graphics_context.as_ref().unwrap().is_device_lost() {
+                self.recover_from_device_lost()?;
+            }
+            let offscreen_views = self
+                .graphics_context
+                .as_ref()
+                .unwrap()
+                .offscreen_data
+                .as_ref()
+                .unwrap()
+                .acquire();
+            self.render_frame(&offscreen_views, frame_time_delta);
+
+            let graphics_context = self.graphics_context.as_ref().unwrap();
+            if let Some(frame_recorder) = self.frame_recorder.as_mut() {
+                frame_recorder.record(&graphics_context.capture_offscreen_target()?)?;
+            }
+        }
+
+        let graphics_context = self.graphics_context.as_ref().unwrap();
+        graphics_context
+            .device
+            .poll(PollType::Wait)
+            .context("Failed to wait for rendering to finish")?;
+
+        if let Some(output_path) = &headless_settings.output_path {
+            graphics_context
+                .capture_offscreen_target()?
+                .save_png(output_path)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn graphics_context(&self) -> Option<&GraphicsContext> {
+        self.graphics_context.as_ref()
+    }
+
+    // Runs the fixed timestep updates due, then renders one frame to the target views and submits it
+    fn render_frame(&mut self, target_views: &TargetViews, frame_time_delta: Duration) {
+        let graphics_context = self.graphics_context.as_mut().unwrap();
+        let sample_context = self.sample_context.as_mut().unwrap();
+
+        self.input.begin_frame(frame_time_delta);
+        // Follows resizes and camera switches, before the sample picks with the camera in on_input
+        let target_size = graphics_context.target_size();
+        let reverse_z = graphics_context.reverse_z();
+        if let Some(camera) = sample_context.process_camera_input() {
+            if let Some(camera_state) = self.pending_camera_state.take() {
+                camera.set_state(&camera_state);
+            }
+            camera.sync_projection(target_size, reverse_z);
+        }
+        sample_context.on_input(graphics_context, &self.input);
+        if let Some(camera) = sample_context.process_camera_input() {
+            // on_input may have switched to another camera
+            camera.sync_projection(target_size, reverse_z);
+            if self.sample_requirements.camera_bookmarks {
+                self.camera_bookmarks.process_input(camera, &self.input);
+            }
+            camera.process_input(&self.input);
+        }
+        self.input.end_frame();
+
+        let update_timestep =
+            Duration::from_secs_f64(1.0 / self.sample_requirements.update_rate.max(1) as f64);
+        self.update_accumulator += frame_time_delta;
+        let mut update_steps = 0;
+        while self.update_accumulator >= update_timestep {
+            if update_steps == self.sample_requirements.max_update_steps.max(1) {
+                // Too slow to catch up, drop the remaining time instead of falling further behind
+                log::debug!(
+                    "Dropped {:?} of simulation time in frame {}",
+                    self.update_accumulator,
+                    self.frame_index
+                );
+                self.update_accumulator = Duration::ZERO;
+                break;
+            }
+            sample_context.update(update_timestep);
+            self.update_accumulator -= update_timestep;
+            update_steps += 1;
+        }
+        let interpolation = self.update_accumulator.as_secs_f32() / update_timestep.as_secs_f32();
+
+        self.elapsed += frame_time_delta;
+        let mut frame = graphics_context.begin_frame(
+            target_views,
+            self.frame_index,
+            self.elapsed,
+            frame_time_delta,
+            interpolation,
+        );
+        graphics_context.push_frame_error_scope();
+        sample_context.render(graphics_context, &mut frame);
+        graphics_context.end_frame(frame, &target_views.view);
+        graphics_context.pop_frame_error_scope(self.frame_index);
+        self.frame_index += 1;
+    }
+
+    // Drops the sample and the lost graphics context, then creates both again
+    fn recover_from_device_lost(&mut self) -> anyhow::Result<()> {
+        log::warn!("Device lost, recreating graphics context and sample context");
+
+        // The sample owns resources of the lost device, drop it first
+        // but keep the viewpoint for the recreated sample
+        if let Some(camera) = self
+            .sample_context
+            .as_mut()
+            .and_then(|sample_context| sample_context.process_camera_input())
+        {
+            self.pending_camera_state = Some(camera.state());
+        }
+        self.sample_context = None;
+        let graphics_context = self
+            .graphics_context
             .take()
             .unwrap()
-            .run_app(self)
-            .expect("Failed to run sample app");
+            .recreate(self.sample_name, &self.sample_requirements)
+            .context("Failed to recreate graphics context")?;
+        let sample_context =
+            S::new(&graphics_context).context("Failed to recreate sample context")?;
+
+        self.graphics_context = Some(graphics_context);
+        self.sample_context = Some(sample_context);
+
+        Ok(())
     }
 }
 
@@ -55,10 +295,7 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
         let graphics_context = match graphics_context {
             Ok(graphics_context) => graphics_context,
             Err(err) => {
-                log::error!("Failed to create graphics context");
-                for err in err.chain() {
-                    log::error!("{err}");
-                }
+                log_error_chain("Failed to create graphics context", &err);
                 event_loop.exit();
                 return;
             }
@@ -68,10 +305,7 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
         let sample_context = match sample_context {
             Ok(sample_context) => sample_context,
             Err(err) => {
-                log::error!("Failed to create sample context");
-                for err in err.chain() {
-                    log::error!("{err}");
-                }
+                log_error_chain("Failed to create sample context", &err);
                 event_loop.exit();
                 return;
             }
@@ -91,24 +325,99 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
             return;
         }
 
+        // The sample sees every event first, a consumed input event is not handled by SampleApp or the camera
+        let consumed = match (&event, self.sample_context.as_mut()) {
+            (WindowEvent::RedrawRequested, _) | (_, None) => false,
+            (_, Some(sample_context)) => {
+                sample_context.on_window_event(self.graphics_context.as_ref().unwrap(), &event)
+            }
+        };
+        // Releases always reach Input, otherwise a consumed release would leave the key held down
+        let is_release = match &event {
+            WindowEvent::KeyboardInput { event, .. } => event.state == ElementState::Released,
+            WindowEvent::MouseInput { state, .. } => *state == ElementState::Released,
+            _ => false,
+        };
+        if !consumed || is_release {
+            self.input.process_window_event(&event);
+        }
+
         match event {
             WindowEvent::RedrawRequested => {
+                if self.graphics_context.as_ref().unwrap().is_device_lost() {
+                    if let Err(err) = self.recover_from_device_lost() {
+                        log_error_chain("Failed to recover from device loss", &err);
+                        event_loop.exit();
+                        return;
+                    }
+                    self.graphics_context
+                        .as_ref()
+                        .unwrap()
+                        .window
+                        .as_ref()
+                        .unwrap()
+                        .request_redraw();
+                    return;
+                }
+
                 let graphics_context = self.graphics_context.as_mut().unwrap();
-                let sample_context = self.sample_context.as_mut().unwrap();
+
+                let surface_data = graphics_context.surface_data.as_mut().unwrap();
+                let surface_frame = match surface_data.acquire() {
+                    Ok(Some(frame)) => frame,
+                    // Skipped frame, the time spent minimized is not passed to the sample
+                    Ok(None) => {
+                        graphics_context.last_frame_time = Instant::now();
+                        // A minimized window is redrawn after the next Resized event
+                        if !surface_data.is_minimized() {
+                            graphics_context.window.as_ref().unwrap().request_redraw();
+                        }
+                        return;
+                    }
+                    Err(err) => {
+                        log_error_chain("Failed to acquire surface texture", &err.into());
+                        event_loop.exit();
+                        return;
+                    }
+                };
 
                 let now = Instant::now();
-                let frame_time_delta = now - graphics_context.last_frame_time;
+                let frame_time_delta = match &self.frame_recorder {
+                    // Simulate a fixed timestep so the recording plays back smoothly
+                    Some(frame_recorder) => frame_recorder.frame_time_delta(),
+                    None => now - graphics_context.last_frame_time,
+                };
                 graphics_context.last_frame_time = now;
 
-                let (surface_texture, surface_texture_view) =
-                    graphics_context.surface_data.acquire();
-
-                sample_context.render(graphics_context, surface_texture_view, frame_time_delta);
-                graphics_context.window.pre_present_notify();
-                surface_texture.present();
+                self.render_frame(&surface_frame.views, frame_time_delta);
 
                 let graphics_context = self.graphics_context.as_ref().unwrap();
-                graphics_context.window.request_redraw();
+                if self.capture_requested {
+                    self.capture_requested = false;
+                    let screenshot_path = screenshot_path(self.sample_name);
+                    let result = graphics_context
+                        .capture_frame(&surface_frame.surface_texture)
+                        .and_then(|image| image.save_png(&screenshot_path));
+                    match result {
+                        Ok(()) => log::info!("Saved screenshot to {}", screenshot_path.display()),
+                        Err(err) => log_error_chain("Failed to capture frame", &err),
+                    }
+                }
+
+                if let Some(frame_recorder) = self.frame_recorder.as_mut() {
+                    let result = graphics_context
+                        .capture_frame(&surface_frame.surface_texture)
+                        .and_then(|image| frame_recorder.record(&image));
+                    if let Err(err) = result {
+                        log_error_chain("Failed to record frame, recording stopped", &err);
+                        self.frame_recorder = None;
+                    }
+                }
+
+                let window = graphics_context.window.as_ref().unwrap();
+                window.pre_present_notify();
+                surface_frame.surface_texture.present();
+                window.request_redraw();
             }
 
             WindowEvent::Resized(new_size) => {
@@ -116,19 +425,47 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
 
                 graphics_context
                     .surface_data
+                    .as_mut()
+                    .unwrap()
                     .configure(new_size.width, new_size.height);
-                graphics_context.window.request_redraw();
+                graphics_context.window.as_ref().unwrap().request_redraw();
+
+                // Minimizing keeps the previous size
+                if new_size.width != 0
+                    && new_size.height != 0
+                    && let Some(sample_context) = self.sample_context.as_mut()
+                {
+                    sample_context.on_resize(graphics_context, new_size.width, new_size.height);
+                }
             }
 
             WindowEvent::KeyboardInput {
                 device_id: _,
                 event,
                 is_synthetic: _,
-            } => {
-                if let Some(sample_context) = self.sample_context.as_mut() {
-                    if let Some(camera) = sample_context.process_camera_input() {
-                        camera.process_keyboard(event.physical_key, event.state);
-                    }
+            } if !consumed => {
+                // F12 saves the next frame as PNG
+                if event.physical_key == PhysicalKey::Code(KeyCode::F12)
+                    && event.state.is_pressed()
+                    && !event.repeat
+                {
+                    self.capture_requested = true;
+                }
+
+                // F9 switches to the next supported present mode
+                if event.physical_key == PhysicalKey::Code(KeyCode::F9)
+                    && event.state.is_pressed()
+                    && !event.repeat
+                {
+                    let surface_data = self
+                        .graphics_context
+                        .as_mut()
+                        .unwrap()
+                        .surface_data
+                        .as_mut()
+                        .unwrap();
+                    let present_mode = surface_data.cycle_present_mode();
+                    log::info!("Present mode: {present_mode:?}");
                 }
             }
             WindowEvent::CursorEntered { device_id: _ } => {
@@ -139,21 +476,18 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
                 self.mouse_in_window = false;
             }
 
-            WindowEvent::MouseInput {
-                device_id: _,
-                state,
-                button,
-            } => {
-                if self.mouse_in_window {
-                    if let Some(sample_context) = self.sample_context.as_mut() {
-                        if let Some(camera) = sample_context.process_camera_input() {
-                            camera.process_mouse_input(button, state);
-                        }
+            WindowEvent::CloseRequested => {
+                if self.sample_requirements.restore_camera
+                    && let Some(sample_context) = self.sample_context.as_mut()
+                    && let Some(camera) = sample_context.process_camera_input()
+                {
+                    let last_viewpoint_path = self.camera_bookmarks.last_viewpoint_path();
+                    if let Err(err) = camera.state().save(&last_viewpoint_path) {
+                        log_error_chain("Failed to save the last viewpoint", &err);
                     }
                 }
+                event_loop.exit();
             }
-
-            WindowEvent::CloseRequested => event_loop.exit(),
             _ => {}
         }
     }
@@ -164,19 +498,18 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
         _device_id: DeviceId,
         event: DeviceEvent,
     ) {
-        match event {
-            DeviceEvent::MouseMotion {
-                delta: (delta_x, delta_y),
-            } => {
-                if self.mouse_in_window {
-                    if let Some(sample_context) = self.sample_context.as_mut() {
-                        if let Some(camera) = sample_context.process_camera_input() {
-                            camera.process_mouse_motion(delta_x, delta_y);
-                        }
-                    }
-                }
-            }
-            _ => {}
+        let Some(graphics_context) = self.graphics_context.as_ref() else {
+            return;
+        };
+        if let Some(sample_context) = self.sample_context.as_mut()
+            && sample_context.on_device_event(graphics_context, &event)
+        {
+            return;
+        }
+
+        // Mouse motion is reported even when the cursor is outside the window
+        if self.mouse_in_window {
+            self.input.process_device_event(&event);
         }
     }
 }
@@ -184,19 +517,168 @@ impl<S: SampleTrait> ApplicationHandler for SampleApp<S> {
 pub trait SampleTrait: Sized {
     fn new(graphics_context: &GraphicsContext) -> anyhow::Result<Self>;
 
-    fn render(
+    // Called with a fixed timestep of 1 / SampleRequirements::update_rate, zero or more times per frame
+    fn update(&mut self, _update_timestep: Duration) {}
+
+    // Record the commands into frame.encoder, it is submitted after render returns
+    fn render(&mut self, graphics_context: &GraphicsContext, frame: &mut Frame);
+
+    // Called for every window event except RedrawRequested, before SampleApp handles it
+    // Return true to consume an input event, then hotkeys and the camera do not see it
+    // Key and mouse button releases are still passed to Input, so nothing stays held down
+    fn on_window_event(
         &mut self,
-        graphics_context: &GraphicsContext,
-        surface_texture_view: TextureView,
-        frame_time_delta: Duration,
-    );
+        _graphics_context: &GraphicsContext,
+        _event: &WindowEvent,
+    ) -> bool {
+        false
+    }
 
-    fn process_camera_input(&mut self) -> Option<&mut Camera> {
+    // Return true to consume the event, then the camera does not see it
+    fn on_device_event(
+        &mut self,
+        _graphics_context: &GraphicsContext,
+        _event: &DeviceEvent,
+    ) -> bool {
+        false
+    }
+
+    // Called once per frame before SampleTrait::update, see input::Input for the per frame state
+    // The projection of the camera from process_camera_input already follows the render target
+    fn on_input(&mut self, _graphics_context: &GraphicsContext, _input: &Input) {}
+
+    // Called after the surface was resized, not when the window is minimized
+    fn on_resize(&mut self, _graphics_context: &GraphicsContext, _width: u32, _height: u32) {}
+
+    fn process_camera_input(&mut self) -> Option<&mut dyn CameraTrait> {
         None
     }
 }
 
-#[derive(Default)]
 pub struct SampleRequirements {
-    pub device_descriptor: Option<DeviceDescriptor<'static>>,
+    // Device features and limits, see graphics_context/device_negotiation.rs
+    // The granted ones are available from GraphicsContext::features and GraphicsContext::limits
+    pub required_features: Features,
+    pub optional_features: Features,
+    pub minimum_limits: Limits,
+    // Granted as far as the adapter supports them, None to use minimum_limits
+    pub preferred_limits: Option<Limits>,
+    // Adapter selection, each can be overridden with an environment variable,
+    // see graphics_context/adapter_selection.rs
+    pub backends: Backends,
+    pub power_preference: PowerPreference,
+    pub force_fallback_adapter: bool,
+    // Case-insensitive substring of the adapter name
+    pub adapter_name: Option<String>,
+    // Wrap every frame in a validation error scope, so errors are logged with the frame they occurred in
+    pub validation_error_scopes: bool,
+    // Present modes in order of preference, the first one supported by the surface is used
+    // Can be cycled at runtime with F9, see SurfaceData::cycle_present_mode
+    pub present_modes: Vec<PresentMode>,
+    // Hint, will always be clamped to the supported range
+    pub desired_maximum_frame_latency: u32,
+    // Encoding of the views passed to SampleTrait::render, also used for the offscreen target in headless mode
+    pub color_output: ColorOutput,
+    // Render to an extended range surface format if available, see GraphicsContext::is_hdr_active
+    // Otherwise the sample renders to an Rgba16Float texture which is tone mapped to the SDR target,
+    // see GraphicsContext::tone_mapping
+    pub hdr: bool,
+    // Depth(-stencil) texture of the render target size, recreated on resize, see GraphicsContext::depth_view
+    pub depth_format: Option<TextureFormat>,
+    // Depth is cleared to 0.0 and closer fragments have greater depth,
+    // see GraphicsContext::depth_clear_value and GraphicsContext::depth_compare
+    pub reverse_z: bool,
+    // MSAA, the sample renders to a multisampled texture and resolves it to Frame::resolve_target
+    // Lowered to the highest count supported by the color and depth formats, see GraphicsContext::sample_count
+    pub sample_count: u32,
+    // Number of SampleTrait::update calls per second of simulated time
+    pub update_rate: u32,
+    // Limit of SampleTrait::update calls per frame, the simulation slows down instead if it can not keep up
+    // 0 is treated as 1
+    pub max_update_steps: u32,
+    // Saves the camera viewpoint on exit and restores it on the next start with a window,
+    // see SampleTrait::process_camera_input
+    pub restore_camera: bool,
+    // Saves and loads camera viewpoints with the bookmark actions (Ctrl + 1-9 and 1-9 by default),
+    // see SampleTrait::process_camera_input
+    pub camera_bookmarks: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorOutput {
+    // Shaders output linear colors, which are encoded to sRGB when written
+    #[default]
+    Srgb,
+    // Shaders output already encoded colors, which are written as is
+    Linear,
+}
+
+impl Default for SampleRequirements {
+    fn default() -> Self {
+        Self {
+            required_features: Features::empty(),
+            optional_features: Features::empty(),
+            minimum_limits: Limits::default(),
+            preferred_limits: None,
+            backends: Backends::PRIMARY,
+            power_preference: PowerPreference::HighPerformance,
+            force_fallback_adapter: false,
+            adapter_name: None,
+            validation_error_scopes: false,
+            present_modes: vec![PresentMode::FifoRelaxed, PresentMode::Fifo],
+            desired_maximum_frame_latency: 3,
+            color_output: ColorOutput::default(),
+            hdr: false,
+            depth_format: None,
+            reverse_z: false,
+            sample_count: 1,
+            update_rate: 60,
+            max_update_steps: 8,
+            restore_camera: false,
+            camera_bookmarks: false,
+        }
+    }
+}
+
+pub struct HeadlessSettings {
+    pub width: u32,
+    pub height: u32,
+    pub frame_count: u32,
+    // Fixed delta passed to SampleTrait::render instead of the measured one
+    pub frame_time_delta: Duration,
+    // Where to save the last rendered frame as PNG
+    pub output_path: Option<PathBuf>,
+}
+
+impl Default for HeadlessSettings {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            frame_count: 60,
+            frame_time_delta: Duration::from_secs_f64(1.0 / 60.0),
+            output_path: None,
+        }
+    }
+}
+
+// screenshots/<sample name>_<unix time in milliseconds>.png
+fn screenshot_path(sample_name: &str) -> PathBuf {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let file_name = format!("{}_{timestamp}.png", sample_name.replace(' ', "_")).to_lowercase();
+    let screenshots_dir = PathBuf::from("screenshots");
+    if let Err(err) = std::fs::create_dir_all(&screenshots_dir) {
+        log::error!("Failed to create {}: {err}", screenshots_dir.display());
+    }
+    screenshots_dir.join(file_name)
+}
+
+fn log_error_chain(message: &str, err: &anyhow::Error) {
+    log::error!("{message}");
+    for err in err.chain() {
+        log::error!("{err}");
+    }
 }
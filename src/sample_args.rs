@@ -0,0 +1,96 @@
+// Command line arguments shared by all samples
+//
+// --headless           Render offscreen without a window
+// --frames <N>         Number of frames to render in headless mode
+// --size <W>x<H>       Size of the offscreen render target in headless mode
+// --output <PATH>      Save the last frame rendered in headless mode as PNG
+// --record <PATH>      Record every frame to a directory of PNG files or a .y4m file
+// --fps <N>            Frame rate of the recording, frames are simulated with a 1/N timestep
+// --list-adapters      Print available adapters with their features and limits and exit
+// --bindings <PATH>    Load key and mouse bindings, see input/action_map.rs
+// --camera <PATH>      Start from a saved camera viewpoint, see camera/camera_state.rs
+
+use crate::HeadlessSettings;
+use anyhow::{Context, bail};
+use std::path::PathBuf;
+
+const DEFAULT_RECORD_FPS: u32 = 60;
+
+pub(crate) struct SampleArgs {
+    pub headless_settings: Option<HeadlessSettings>,
+    pub record_path: Option<PathBuf>,
+    pub record_fps: u32,
+    pub list_adapters: bool,
+    pub bindings_path: Option<PathBuf>,
+    pub camera_path: Option<PathBuf>,
+}
+
+impl SampleArgs {
+    pub fn parse() -> anyhow::Result<Self> {
+        let mut headless = false;
+        let mut headless_settings = HeadlessSettings::default();
+        let mut record_path = None;
+        let mut record_fps = DEFAULT_RECORD_FPS;
+        let mut list_adapters = false;
+        let mut bindings_path = None;
+        let mut camera_path = None;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--headless" => headless = true,
+                "--list-adapters" => list_adapters = true,
+                "--frames" => {
+                    let value = args.next().context("--frames requires a value")?;
+                    headless_settings.frame_count = value
+                        .parse()
+                        .with_context(|| format!("Invalid frame count: {value}"))?;
+                }
+                "--size" => {
+                    let value = args.next().context("--size requires a value")?;
+                    let (width, height) = value
+                        .split_once('x')
+                        .with_context(|| format!("Invalid size: {value}, expected <W>x<H>"))?;
+                    headless_settings.width = width
+                        .parse()
+                        .with_context(|| format!("Invalid width: {width}"))?;
+                    headless_settings.height = height
+                        .parse()
+                        .with_context(|| format!("Invalid height: {height}"))?;
+                }
+                "--output" => {
+                    let value = args.next().context("--output requires a value")?;
+                    headless_settings.output_path = Some(value.into());
+                }
+                "--record" => {
+                    let value = args.next().context("--record requires a value")?;
+                    record_path = Some(value.into());
+                }
+                "--fps" => {
+                    let value = args.next().context("--fps requires a value")?;
+                    record_fps = value
+                        .parse()
+                        .with_context(|| format!("Invalid frame rate: {value}"))?;
+                }
+                "--bindings" => {
+                    let value = args.next().context("--bindings requires a value")?;
+                    bindings_path = Some(value.into());
+                }
+                "--camera" => {
+                    let value = args.next().context("--camera requires a value")?;
+                    camera_path = Some(value.into());
+                }
+                _ => bail!("Unknown argument: {arg}"),
+            }
+        }
+
+        Ok(Self {
+            headless_settings: headless.then_some(headless_settings),
+            record_path,
+            record_fps,
+            list_adapters,
+            bindings_path,
+            camera_path,
+        })
+    }
+}
@@ -0,0 +1,152 @@
+// Loading key bindings, no graphics adapter needed
+
+use graphics_samples::input::action_map::{
+    ActionMap, BOOKMARK_SAVE, BOOKMARK_SLOTS, Binding, CAMERA_PAN, CAMERA_ROTATE, MOVE_BACK,
+    MOVE_DOWN, MOVE_FAST, MOVE_FORWARD, MOVE_LEFT, MOVE_RIGHT, MOVE_SLOW, MOVE_UP, ROLL_LEFT,
+    ROLL_RIGHT,
+};
+use winit::event::MouseButton;
+use winit::keyboard::KeyCode;
+
+#[test]
+fn default_bindings_do_not_overlap() {
+    // Holding the key of one action must not trigger another, e.g. saving a bookmark must not speed up the camera
+    let actions = [
+        MOVE_FORWARD,
+        MOVE_BACK,
+        MOVE_RIGHT,
+        MOVE_LEFT,
+        MOVE_UP,
+        MOVE_DOWN,
+        MOVE_FAST,
+        MOVE_SLOW,
+        ROLL_LEFT,
+        ROLL_RIGHT,
+        CAMERA_ROTATE,
+        CAMERA_PAN,
+        BOOKMARK_SAVE,
+    ]
+    .into_iter()
+    .chain(BOOKMARK_SLOTS);
+
+    let action_map = ActionMap::default();
+    let mut bound: Vec<(Binding, &str)> = Vec::new();
+    for action in actions {
+        for binding in action_map.bindings(action) {
+            if let Some((_, other)) = bound.iter().find(|(other, _)| other == binding) {
+                panic!("{binding:?} is bound to both {other} and {action}");
+            }
+            bound.push((*binding, action));
+        }
+    }
+}
+
+#[test]
+fn parse_replaces_listed_actions_and_keeps_the_others() {
+    let action_map = ActionMap::parse("move_forward = KeyE, ArrowUp").unwrap();
+
+    assert_eq!(
+        action_map.bindings(MOVE_FORWARD),
+        [Binding::Key(KeyCode::KeyE), Binding::Key(KeyCode::ArrowUp)]
+    );
+    assert_eq!(
+        action_map.bindings(MOVE_BACK),
+        ActionMap::default().bindings(MOVE_BACK)
+    );
+}
+
+#[test]
+fn parse_skips_comments_and_empty_lines() {
+    let text = "# ESDF\n\n  # indented comment\nmove_back = KeyD\n";
+    let action_map = ActionMap::parse(text).unwrap();
+
+    assert_eq!(
+        action_map.bindings(MOVE_BACK),
+        [Binding::Key(KeyCode::KeyD)]
+    );
+}
+
+#[test]
+fn parse_mouse_buttons() {
+    let action_map = ActionMap::parse("camera_rotate = MouseRight, MouseBack").unwrap();
+
+    assert_eq!(
+        action_map.bindings(CAMERA_ROTATE),
+        [
+            Binding::MouseButton(MouseButton::Right),
+            Binding::MouseButton(MouseButton::Back)
+        ]
+    );
+}
+
+#[test]
+fn parse_unbinds_an_action_without_bindings() {
+    let action_map = ActionMap::parse("move_forward =").unwrap();
+
+    assert!(action_map.bindings(MOVE_FORWARD).is_empty());
+}
+
+#[test]
+fn parse_adds_unknown_actions() {
+    let action_map = ActionMap::parse("toggle_wireframe = KeyG").unwrap();
+
+    assert_eq!(
+        action_map.bindings("toggle_wireframe"),
+        [Binding::Key(KeyCode::KeyG)]
+    );
+    assert!(ActionMap::default().bindings("toggle_wireframe").is_empty());
+}
+
+#[test]
+fn parse_rebinds_bookmarks() {
+    let action_map = ActionMap::parse("bookmark_save = ControlRight\nbookmark_1 = F1").unwrap();
+
+    assert_eq!(
+        action_map.bindings(BOOKMARK_SAVE),
+        [Binding::Key(KeyCode::ControlRight)]
+    );
+    assert_eq!(
+        action_map.bindings(BOOKMARK_SLOTS[0]),
+        [Binding::Key(KeyCode::F1)]
+    );
+    assert_eq!(
+        action_map.bindings(BOOKMARK_SLOTS[8]),
+        [Binding::Key(KeyCode::Digit9)]
+    );
+}
+
+#[test]
+fn parse_rejects_unknown_keys() {
+    for text in [
+        "move_forward = KeyE, KeyComma",
+        "move_forward = keye",
+        "camera_rotate = Mouse4",
+    ] {
+        let err = ActionMap::parse(text).err().unwrap();
+        assert!(
+            format!("{err:#}").contains("unknown key or button"),
+            "{text}: {err:#}"
+        );
+    }
+}
+
+#[test]
+fn parse_rejects_lines_without_equals_sign() {
+    let err = ActionMap::parse("move_forward = KeyE\nmove_back KeyD")
+        .err()
+        .unwrap();
+
+    assert!(format!("{err:#}").contains("Line 2"), "{err:#}");
+}
+
+#[test]
+fn load_reports_the_file() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("invalid_bindings.txt");
+    std::fs::write(&path, "move_forward = NotAKey").unwrap();
+
+    let err = ActionMap::load(&path).err().unwrap();
+    assert!(
+        format!("{err:#}").contains("invalid_bindings.txt"),
+        "{err:#}"
+    );
+}
@@ -0,0 +1,37 @@
+// Fly camera orientation, no graphics adapter needed
+
+use graphics_samples::camera::Camera;
+use nalgebra::{Point3, Vector3};
+use std::time::Duration;
+
+#[test]
+fn initial_front_is_kept() {
+    for front in [
+        [0.0, 0.0, 1.0],
+        [0.0, 1.0, 1.0],
+        [0.0, -1.0, 1.0],
+        [1.0, 0.5, -2.0],
+        [-3.0, -1.0, 0.5],
+    ] {
+        let mut camera = Camera::new([1.0, 2.0, 3.0], front, 1.0, 1.0, None);
+        let expected = Vector3::from(front).normalize();
+
+        let actual = camera.orientation().transform_vector(&Vector3::z());
+        assert!(
+            (actual - expected).norm() < 1.0e-5,
+            "{front:?}: {actual:?} != {expected:?}"
+        );
+        // Positive pitch looks down
+        assert_eq!(camera.pitch() < 0.0, front[1] > 0.0, "{front:?}");
+
+        // A point in front of the camera stays in front of it after the first frame
+        let ahead = Point3::from([1.0, 2.0, 3.0]) + expected * 5.0;
+        let view_point = camera
+            .calculate_view_matrix(Duration::ZERO)
+            .transform_point(&ahead);
+        assert!(
+            (view_point.coords - Vector3::new(0.0, 0.0, 5.0)).norm() < 1.0e-4,
+            "{front:?}: {view_point:?}"
+        );
+    }
+}
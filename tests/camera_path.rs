@@ -0,0 +1,207 @@
+// Camera path playback and parsing, no graphics adapter needed
+
+use graphics_samples::camera::Camera;
+use graphics_samples::camera::camera_path::{CameraPath, CameraPathMode};
+use nalgebra::{UnitQuaternion, Vector3};
+use std::time::Duration;
+
+// Time, position, yaw, pitch and roll, with a curve and a turn so the spline is not a straight line
+const PATH: &str = "\
+0.0 0.0 0.0 0.0 0.0 0.0 0.0
+1.0 1.0 0.5 2.0 30.0 10.0 0.0
+2.5 3.0 0.0 2.5 90.0 -5.0 0.0
+4.0 4.0 1.0 0.0 120.0 0.0 0.0
+";
+
+fn camera() -> Camera {
+    Camera::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], 1.0, 1.0, None)
+}
+
+fn assert_near(actual: [f32; 3], expected: [f32; 3]) {
+    assert!(
+        (Vector3::from(actual) - Vector3::from(expected)).norm() < 1.0e-4,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+fn assert_same_orientation(actual: UnitQuaternion<f32>, expected: UnitQuaternion<f32>) {
+    assert!(
+        actual.angle_to(&expected) < 1.0e-3,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn sample_passes_through_keyframes() {
+    let camera_path = CameraPath::parse(PATH).unwrap();
+    assert_eq!(camera_path.keyframes.len(), 4);
+    for keyframe in &camera_path.keyframes {
+        let (position, orientation) = camera_path.sample(keyframe.time).unwrap();
+        assert_near(position, keyframe.position);
+        assert_same_orientation(orientation, keyframe.orientation);
+    }
+}
+
+#[test]
+fn sample_clamps_to_the_path() {
+    let camera_path = CameraPath::parse(PATH).unwrap();
+    let (first, last) = (
+        camera_path.keyframes.first().unwrap(),
+        camera_path.keyframes.last().unwrap(),
+    );
+
+    let (position, orientation) = camera_path.sample(-1.0).unwrap();
+    assert_near(position, first.position);
+    assert_same_orientation(orientation, first.orientation);
+
+    let (position, orientation) = camera_path.sample(100.0).unwrap();
+    assert_near(position, last.position);
+    assert_same_orientation(orientation, last.orientation);
+
+    assert!(CameraPath::default().sample(0.0).is_none());
+}
+
+#[test]
+fn sample_between_keyframes_is_continuous() {
+    let camera_path = CameraPath::parse(PATH).unwrap();
+    for keyframe in &camera_path.keyframes[1..camera_path.keyframes.len() - 1] {
+        let (before, _) = camera_path.sample(keyframe.time - 1.0e-3).unwrap();
+        let (after, _) = camera_path.sample(keyframe.time + 1.0e-3).unwrap();
+        assert!((Vector3::from(before) - Vector3::from(after)).norm() < 0.05);
+    }
+}
+
+#[test]
+fn orientation_turns_smoothly_between_opposite_keyframes() {
+    let camera_path = CameraPath::parse("0.0 0 0 0 0 0 0\n1.0 0 0 0 180 0 0").unwrap();
+    let (start, end) = (
+        camera_path.keyframes[0].orientation,
+        camera_path.keyframes[1].orientation,
+    );
+    for step in 1..10 {
+        let t = step as f32 / 10.0;
+        let (_, orientation) = camera_path.sample(t).unwrap();
+        assert!((orientation.angle_to(&start) - t * std::f32::consts::PI).abs() < 1.0e-3);
+        assert!((orientation.angle_to(&end) - (1.0 - t) * std::f32::consts::PI).abs() < 1.0e-3);
+    }
+}
+
+#[test]
+fn velocity_is_continuous_across_unequal_intervals() {
+    // Keyframe 1 is 1.0 s after keyframe 0 but 1.5 s before keyframe 2
+    let camera_path = CameraPath::parse(PATH).unwrap();
+    let velocity = |from: f32, to: f32| {
+        let (from_position, _) = camera_path.sample(from).unwrap();
+        let (to_position, _) = camera_path.sample(to).unwrap();
+        (Vector3::from(to_position) - Vector3::from(from_position)) / (to - from)
+    };
+    let delta = 1.0e-3;
+    for keyframe in &camera_path.keyframes[1..camera_path.keyframes.len() - 1] {
+        let before = velocity(keyframe.time - delta, keyframe.time);
+        let after = velocity(keyframe.time, keyframe.time + delta);
+        assert!(
+            (before - after).norm() < 0.02,
+            "at {}: {before:?} != {after:?}",
+            keyframe.time
+        );
+    }
+}
+
+#[test]
+fn parse_rejects_decreasing_times() {
+    let err = CameraPath::parse("0.0 0 0 0 0 0 0\n2.0 1 0 0 0 0 0\n1.0 2 0 0 0 0 0")
+        .err()
+        .unwrap();
+    assert!(format!("{err:#}").contains("Line 3"), "{err:#}");
+
+    // Equal times are allowed, e.g. for a cut
+    assert!(CameraPath::parse("0.0 0 0 0 0 0 0\n1.0 1 0 0 0 0 0\n1.0 2 0 0 0 0 0").is_ok());
+}
+
+#[test]
+fn parse_rejects_malformed_lines() {
+    for text in ["0.0 0 0 0 0 0", "0.0 0 0 0 0 0 0 0", "0.0 x 0 0 0 0 0"] {
+        assert!(CameraPath::parse(text).is_err(), "{text}");
+    }
+    // Comments and empty lines are skipped
+    let camera_path = CameraPath::parse("# start\n\n0.0 0 0 0 0 0 0\n").unwrap();
+    assert_eq!(camera_path.keyframes.len(), 1);
+}
+
+#[test]
+fn save_and_load() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR")).join("camera_path.txt");
+    let camera_path = CameraPath::parse(PATH).unwrap();
+    camera_path.save(&path).unwrap();
+
+    let loaded = CameraPath::load(&path).unwrap();
+    assert_eq!(loaded.keyframes.len(), camera_path.keyframes.len());
+    for (loaded, keyframe) in loaded.keyframes.iter().zip(&camera_path.keyframes) {
+        assert_eq!(loaded.time, keyframe.time);
+        assert_near(loaded.position, keyframe.position);
+        assert_same_orientation(loaded.orientation, keyframe.orientation);
+    }
+}
+
+#[test]
+fn playback_stops_at_the_end() {
+    let mut camera_path = CameraPath::parse(PATH).unwrap();
+    let mut camera = camera();
+    camera_path.start_playback();
+    assert_eq!(camera_path.mode(), CameraPathMode::Playing);
+
+    camera_path.update(&mut camera, Duration::from_secs(10));
+    assert_eq!(camera_path.mode(), CameraPathMode::Idle);
+    assert_near(camera.position(), camera_path.keyframes[3].position);
+}
+
+#[test]
+fn looping_playback_wraps_around() {
+    let mut camera_path = CameraPath::parse(PATH).unwrap();
+    camera_path.looping = true;
+    let mut camera = camera();
+    camera_path.start_playback();
+
+    camera_path.update(&mut camera, Duration::from_secs_f32(5.0));
+    assert_eq!(camera_path.mode(), CameraPathMode::Playing);
+    assert_near(camera.position(), camera_path.sample(1.0).unwrap().0);
+}
+
+#[test]
+fn playback_of_a_path_starting_later() {
+    let mut camera_path =
+        CameraPath::parse("2.0 0 0 0 0 0 0\n3.0 1 0 0 0 0 0\n5.0 3 0 0 0 0 0").unwrap();
+    assert_eq!(camera_path.duration(), 3.0);
+    let mut camera = camera();
+    camera_path.start_playback();
+
+    camera_path.update(&mut camera, Duration::from_secs_f32(1.0));
+    assert_eq!(camera_path.mode(), CameraPathMode::Playing);
+    assert_near(camera.position(), camera_path.keyframes[1].position);
+
+    camera_path.update(&mut camera, Duration::from_secs_f32(2.0));
+    assert_eq!(camera_path.mode(), CameraPathMode::Idle);
+    assert_near(camera.position(), camera_path.keyframes[2].position);
+}
+
+#[test]
+fn zero_duration_path_does_not_play() {
+    let mut camera_path = CameraPath::parse("0.0 0 0 0 0 0 0\n0.0 1 0 0 0 0 0").unwrap();
+    camera_path.looping = true;
+    camera_path.start_playback();
+    assert_eq!(camera_path.mode(), CameraPathMode::Idle);
+}
+
+#[test]
+fn looping_zero_duration_path_stops() {
+    let mut camera_path = CameraPath::parse(PATH).unwrap();
+    camera_path.looping = true;
+    let mut camera = camera();
+    camera_path.start_playback();
+
+    // Keyframes replaced during playback
+    camera_path.keyframes.truncate(1);
+    camera_path.update(&mut camera, Duration::from_millis(16));
+    assert_eq!(camera_path.mode(), CameraPathMode::Idle);
+    assert_near(camera.position(), camera_path.keyframes[0].position);
+}
@@ -0,0 +1,164 @@
+// Camera state files, no graphics adapter needed
+
+use graphics_samples::camera::camera_state::CameraState;
+use graphics_samples::camera::projection::Projection;
+use graphics_samples::camera::{Camera, CameraTrait};
+
+const VALID: &str = "\
+position = [0.0, 1.5, -4.0]
+yaw = 12.5
+pitch = 20.0
+roll = 0.0
+sensitivity = 1.0
+move_speed = 1.0
+projection = \"perspective\"
+fov_y = 45.0
+z_near = 0.1
+z_far = 100.0
+";
+
+fn state(projection: Projection) -> CameraState {
+    CameraState {
+        position: [1.0, -2.5, 0.1],
+        yaw: 33.3,
+        pitch: -12.25,
+        roll: 5.0,
+        sensitivity: 0.7,
+        move_speed: Some(2.5),
+        orbit_distance: None,
+        projection,
+    }
+}
+
+fn parse_error(text: &str) -> String {
+    format!("{:#}", CameraState::parse(text).err().unwrap())
+}
+
+#[test]
+fn round_trip_every_projection() {
+    for projection in [
+        Projection::perspective(60.0, 1.0, 0.05, 500.0),
+        Projection::infinite_perspective(75.0, 1.0, 0.01),
+        Projection::orthographic(12.0, 1.0, 0.5, 50.0),
+    ] {
+        let state = state(projection);
+        assert_eq!(CameraState::parse(&state.to_text()).unwrap(), state);
+    }
+}
+
+#[test]
+fn round_trip_optional_values() {
+    let state = CameraState {
+        move_speed: None,
+        orbit_distance: Some(7.5),
+        ..state(Projection::default())
+    };
+    assert_eq!(CameraState::parse(&state.to_text()).unwrap(), state);
+}
+
+#[test]
+fn save_and_load() {
+    let path = std::path::Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join("camera_states")
+        .join("saved.toml");
+    let state = state(Projection::default());
+    state.save(&path).unwrap();
+    assert_eq!(CameraState::load(&path).unwrap(), state);
+}
+
+#[test]
+fn parse_toml_comments_and_trailing_commas() {
+    let text = "\
+# Viewpoint of the bug report
+position = [0.0, 1.5, -4.0,] # trailing comma
+yaw = 12.5 # degrees
+pitch = 20.0
+sensitivity = 1.0
+projection = \"perspective\" # \"#\" inside a string is not a comment
+fov_y = 45.0
+z_near = 0.1
+z_far = 100.0
+";
+    let state = CameraState::parse(text).unwrap();
+    assert_eq!(state.position, [0.0, 1.5, -4.0]);
+    assert_eq!(state.yaw, 12.5);
+    // Roll is optional
+    assert_eq!(state.roll, 0.0);
+    assert_eq!(state.move_speed, None);
+}
+
+#[test]
+fn parse_missing_keys() {
+    for key in [
+        "position",
+        "yaw",
+        "pitch",
+        "sensitivity",
+        "projection",
+        "fov_y",
+        "z_near",
+        "z_far",
+    ] {
+        let text: String = VALID
+            .lines()
+            .filter(|line| !line.starts_with(&format!("{key} ")))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let err = parse_error(&text);
+        assert!(err.contains(&format!("Missing {key}")), "{key}: {err}");
+    }
+}
+
+#[test]
+fn parse_invalid_values() {
+    for (invalid, expected) in [
+        ("yaw = 12.5", "yaw = twelve", "yaw is not a number"),
+        (
+            "projection = \"perspective\"",
+            "projection = \"fisheye\"",
+            "Unknown projection fisheye",
+        ),
+        (
+            "position = [0.0, 1.5, -4.0]",
+            "position = 0.0",
+            "position is not an array",
+        ),
+        (
+            "position = [0.0, 1.5, -4.0]",
+            "position = [0.0, 1.5]",
+            "position needs 3 components",
+        ),
+        (
+            "position = [0.0, 1.5, -4.0]",
+            "position = [0.0, x, -4.0]",
+            "not an array of numbers",
+        ),
+        (
+            "pitch = 20.0",
+            "pitch 20.0",
+            "Line 3: expected <key> = <value>",
+        ),
+    ]
+    .map(|(valid_line, invalid_line, expected)| (VALID.replace(valid_line, invalid_line), expected))
+    {
+        let err = parse_error(&invalid);
+        assert!(err.contains(expected), "{invalid}: {err}");
+    }
+}
+
+#[test]
+fn set_state_keeps_aspect_and_reverse_z() {
+    let mut camera = Camera::new([0.0, 0.0, 0.0], [0.0, 0.0, 1.0], 1.0, 1.0, None);
+    let projection = camera.projection_mut();
+    projection.aspect = 16.0 / 9.0;
+    projection.reverse_z = true;
+
+    let state = state(Projection::orthographic(12.0, 1.0, 0.5, 50.0));
+    camera.set_state(&state);
+
+    let projection = camera.projection();
+    assert_eq!(projection.kind, state.projection.kind);
+    assert_eq!(projection.z_near, state.projection.z_near);
+    assert_eq!(projection.aspect, 16.0 / 9.0);
+    assert!(projection.reverse_z);
+}
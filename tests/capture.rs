@@ -0,0 +1,36 @@
+// Conversion of read back texels to 8-bit RGBA, no graphics adapter needed
+
+use graphics_samples::graphics_context::capture::CapturedImage;
+use wgpu::TextureFormat;
+
+#[test]
+fn bgra_is_swapped_to_rgba() {
+    for format in [TextureFormat::Bgra8Unorm, TextureFormat::Bgra8UnormSrgb] {
+        let image = CapturedImage::from_texels(format, 1, 1, &[10, 20, 30, 40]).unwrap();
+        assert_eq!(image.rgba, [30, 20, 10, 40]);
+    }
+}
+
+#[test]
+fn rgb10a2_is_reduced_to_8_bits() {
+    // Red 1023, green 512, blue 0, alpha 3
+    let bits: u32 = 1023 | (512 << 10) | (3 << 30);
+    let image =
+        CapturedImage::from_texels(TextureFormat::Rgb10a2Unorm, 1, 1, &bits.to_le_bytes()).unwrap();
+    assert_eq!(image.rgba, [255, 128, 0, 255]);
+}
+
+#[test]
+fn rgba16float_is_encoded_to_srgb_and_clipped() {
+    // 1.0, linear 0.5 (sRGB 0.735), 2.0 above SDR white, -1.0 below black
+    let pixel = [0x3c00_u16, 0x3800, 0x4000, 0xbc00];
+    let texels: Vec<u8> = pixel.iter().flat_map(|half| half.to_le_bytes()).collect();
+    let image = CapturedImage::from_texels(TextureFormat::Rgba16Float, 1, 1, &texels).unwrap();
+    assert_eq!(image.rgba, [255, 188, 255, 0]);
+}
+
+#[test]
+fn unsupported_format_and_size_mismatch_fail() {
+    assert!(CapturedImage::from_texels(TextureFormat::R8Unorm, 1, 1, &[0]).is_err());
+    assert!(CapturedImage::from_texels(TextureFormat::Rgba8Unorm, 2, 1, &[0; 4]).is_err());
+}
@@ -0,0 +1,100 @@
+// Frustum planes and intersection tests, no graphics adapter needed
+
+use graphics_samples::camera::frustum::Frustum;
+use graphics_samples::camera::projection::Projection;
+use nalgebra::{Matrix4, Point3, Vector3};
+
+// Camera at the origin looking along +Z
+fn view_matrix() -> Matrix4<f32> {
+    Matrix4::look_at_lh(
+        &Point3::origin(),
+        &Point3::new(0.0, 0.0, 1.0),
+        &Vector3::y(),
+    )
+}
+
+fn projections() -> Vec<Projection> {
+    let perspective = Projection::perspective(90.0, 1.0, 0.1, 10.0);
+    let orthographic = Projection::orthographic(2.0, 1.0, 0.1, 10.0);
+    let mut projections = vec![perspective, orthographic];
+    for projection in [perspective, orthographic] {
+        projections.push(Projection {
+            reverse_z: true,
+            ..projection
+        });
+    }
+    projections
+}
+
+#[test]
+fn contains_point() {
+    for projection in projections() {
+        let frustum = Frustum::new(&view_matrix(), &projection);
+        assert!(frustum.contains_point([0.0, 0.0, 5.0]), "{projection:?}");
+        assert!(frustum.contains_point([0.9, -0.9, 1.0]), "{projection:?}");
+        assert!(!frustum.contains_point([0.0, 0.0, -1.0]), "{projection:?}");
+        assert!(!frustum.contains_point([0.0, 0.0, 0.05]), "{projection:?}");
+        assert!(!frustum.contains_point([0.0, 0.0, 11.0]), "{projection:?}");
+        assert!(!frustum.contains_point([1.1, 0.0, 1.0]), "{projection:?}");
+        assert!(!frustum.contains_point([0.0, 1.1, 1.0]), "{projection:?}");
+    }
+}
+
+#[test]
+fn intersects_sphere_and_aabb() {
+    for projection in projections() {
+        let frustum = Frustum::new(&view_matrix(), &projection);
+        assert!(frustum.intersects_sphere([0.0, 0.0, 5.0], 0.5));
+        // Crossing the far plane
+        assert!(frustum.intersects_sphere([0.0, 0.0, 10.5], 1.0));
+        assert!(!frustum.intersects_sphere([0.0, 0.0, -2.0], 1.0));
+
+        assert!(frustum.intersects_aabb([-0.5, -0.5, 2.0], [0.5, 0.5, 3.0]));
+        // Crossing the near plane
+        assert!(frustum.intersects_aabb([-0.5, -0.5, -1.0], [0.5, 0.5, 1.0]));
+        assert!(!frustum.intersects_aabb([-0.5, -0.5, -3.0], [0.5, 0.5, -2.0]));
+        assert!(!frustum.intersects_aabb([2.0, -0.5, 1.0], [3.0, 0.5, 1.5]));
+    }
+}
+
+#[test]
+fn corners() {
+    let frustum = Frustum::new(
+        &view_matrix(),
+        &Projection::perspective(90.0, 2.0, 0.1, 10.0),
+    );
+    let corners = frustum.corners.unwrap();
+    let expected = [
+        [-0.2, -0.1, 0.1],
+        [0.2, -0.1, 0.1],
+        [0.2, 0.1, 0.1],
+        [-0.2, 0.1, 0.1],
+        [-20.0, -10.0, 10.0],
+        [20.0, -10.0, 10.0],
+        [20.0, 10.0, 10.0],
+        [-20.0, 10.0, 10.0],
+    ];
+    for (corner, expected) in corners.iter().zip(expected) {
+        assert!(
+            (corner - Vector3::from(expected)).norm() < 1.0e-3,
+            "{corner:?} != {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn infinite_perspective() {
+    let projection = Projection::infinite_perspective(90.0, 1.0, 0.1);
+    for projection in [
+        projection,
+        Projection {
+            reverse_z: true,
+            ..projection
+        },
+    ] {
+        let frustum = Frustum::new(&view_matrix(), &projection);
+        assert!(frustum.corners.is_none());
+        assert!(frustum.contains_point([0.0, 0.0, 1.0e6]));
+        assert!(!frustum.contains_point([0.0, 0.0, 0.05]));
+    }
+}
@@ -0,0 +1,142 @@
+// Golden image regression tests
+// Every example is rendered headless for a fixed number of frames with a fixed frame time delta
+// and compared against the reference image in tests/golden/<example>.png
+// Run with UPDATE_GOLDEN=1 to create or update the reference images
+// The adapter is selected like in the examples, so WGPU_BACKEND, WGPU_ADAPTER_NAME etc. apply
+// Without an adapter the tests fail, set SKIP_GOLDEN=1 to skip them instead (e.g. no software rasterizer installed)
+
+use graphics_samples::SampleRequirements;
+use graphics_samples::graphics_context::adapter_selection::AdapterSelection;
+use graphics_samples::graphics_context::capture::CapturedImage;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FRAME_COUNT: u32 = 8;
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 256;
+// Maximum per-channel difference for two pixels to be considered equal
+const PIXEL_TOLERANCE: u8 = 3;
+// Rasterizers are allowed to disagree on triangle edges
+const MAX_MISMATCHED_PIXELS_FRACTION: f64 = 0.002;
+
+#[test]
+fn triangle_raw() {
+    check_example("triangle_raw");
+}
+
+#[test]
+fn triangle() {
+    check_example("triangle");
+}
+
+#[test]
+fn camera() {
+    // The camera sample starts at a fixed position and receives no input in headless mode,
+    // so the view matrix is the same on every run
+    check_example("camera");
+}
+
+fn check_example(example_name: &str) {
+    if let Err(err) = find_adapter() {
+        if std::env::var_os("SKIP_GOLDEN").is_some() {
+            eprintln!("Skipping {example_name}: {err:#}");
+            return;
+        }
+        panic!("{err:#}, set SKIP_GOLDEN=1 to skip the golden image tests");
+    }
+
+    let output_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden_images");
+    std::fs::create_dir_all(&output_dir).expect("Failed to create output directory");
+    let output_path = output_dir.join(format!("{example_name}.png"));
+
+    let status = Command::new(example_binary_path(example_name))
+        .args(["--headless", "--frames", &FRAME_COUNT.to_string()])
+        .args(["--size", &format!("{WIDTH}x{HEIGHT}")])
+        .arg("--output")
+        .arg(&output_path)
+        .status()
+        .expect("Failed to start example, examples are built by `cargo test`");
+    assert!(status.success(), "{example_name} failed with {status}");
+
+    let actual = CapturedImage::load_png(&output_path).expect("Failed to load rendered image");
+
+    let reference_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden");
+    let reference_path = reference_dir.join(format!("{example_name}.png"));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(&reference_dir).expect("Failed to create reference directory");
+        actual
+            .save_png(&reference_path)
+            .expect("Failed to update reference image");
+        return;
+    }
+    let reference = CapturedImage::load_png(&reference_path).unwrap_or_else(|err| {
+        panic!("Failed to load reference image, run with UPDATE_GOLDEN=1 to create it: {err:#}")
+    });
+
+    assert_eq!(
+        (actual.width, actual.height),
+        (reference.width, reference.height),
+        "{example_name} rendered at a different size than the reference"
+    );
+
+    // Mismatched pixels are red in the diff image, matching ones are dimmed
+    let mut diff = CapturedImage {
+        width: actual.width,
+        height: actual.height,
+        rgba: Vec::with_capacity(actual.rgba.len()),
+    };
+    let mut mismatched_pixels = 0;
+    for (actual_pixel, reference_pixel) in actual
+        .rgba
+        .chunks_exact(4)
+        .zip(reference.rgba.chunks_exact(4))
+    {
+        let matches = actual_pixel
+            .iter()
+            .zip(reference_pixel)
+            .all(|(a, r)| a.abs_diff(*r) <= PIXEL_TOLERANCE);
+        if matches {
+            diff.rgba.extend(actual_pixel[..3].iter().map(|c| c / 4));
+            diff.rgba.push(255);
+        } else {
+            mismatched_pixels += 1;
+            diff.rgba.extend_from_slice(&[255, 0, 0, 255]);
+        }
+    }
+
+    let total_pixels = (actual.width * actual.height) as f64;
+    if mismatched_pixels as f64 / total_pixels > MAX_MISMATCHED_PIXELS_FRACTION {
+        let diff_path = output_dir.join(format!("{example_name}_diff.png"));
+        diff.save_png(&diff_path)
+            .expect("Failed to save diff image");
+        panic!(
+            "{example_name}: {mismatched_pixels} of {total_pixels} pixels differ from {}\n\
+            actual: {}\n\
+            diff: {}",
+            reference_path.display(),
+            output_path.display(),
+            diff_path.display(),
+        );
+    }
+}
+
+// None of the examples change the adapter requirements
+fn find_adapter() -> anyhow::Result<()> {
+    let adapter_selection = AdapterSelection::new(&SampleRequirements::default());
+    let instance = adapter_selection.create_instance();
+    adapter_selection.request_adapter(&instance, None)?;
+    Ok(())
+}
+
+// Examples are built next to the test binary: target/<profile>/examples
+fn example_binary_path(example_name: &str) -> PathBuf {
+    let test_binary = std::env::current_exe().expect("Failed to get test binary path");
+    test_binary
+        .parent()
+        .and_then(Path::parent)
+        .expect("Unexpected test binary location")
+        .join("examples")
+        .join(format!("{example_name}{}", std::env::consts::EXE_SUFFIX))
+}
@@ -0,0 +1,89 @@
+// Projection matrices, no graphics adapter needed
+
+use graphics_samples::camera::projection::Projection;
+use nalgebra::Vector4;
+
+const Z_NEAR: f32 = 0.1;
+const Z_FAR: f32 = 100.0;
+
+// Clip space depth z' / w' of a view space point on the Z axis
+fn depth(projection: &Projection, z: f32) -> f32 {
+    let clip = projection.matrix() * Vector4::new(0.0, 0.0, z, 1.0);
+    clip.z / clip.w
+}
+
+fn reversed(projection: Projection) -> Projection {
+    Projection {
+        reverse_z: true,
+        ..projection
+    }
+}
+
+fn assert_near(actual: f32, expected: f32) {
+    assert!((actual - expected).abs() < 1.0e-5, "{actual} != {expected}");
+}
+
+#[test]
+fn perspective_depth_range() {
+    let projection = Projection::perspective(60.0, 1.5, Z_NEAR, Z_FAR);
+    assert_near(depth(&projection, Z_NEAR), 0.0);
+    assert_near(depth(&projection, Z_FAR), 1.0);
+
+    let projection = reversed(projection);
+    assert_near(depth(&projection, Z_NEAR), 1.0);
+    assert_near(depth(&projection, Z_FAR), 0.0);
+}
+
+#[test]
+fn infinite_perspective_depth_range() {
+    let projection = Projection::infinite_perspective(60.0, 1.5, Z_NEAR);
+    assert_near(depth(&projection, Z_NEAR), 0.0);
+    // Approaches the far value without reaching it
+    let far_depth = depth(&projection, 1.0e6);
+    assert!(far_depth < 1.0 && far_depth > 0.999, "{far_depth}");
+
+    let projection = reversed(projection);
+    assert_near(depth(&projection, Z_NEAR), 1.0);
+    let far_depth = depth(&projection, 1.0e6);
+    assert!(far_depth > 0.0 && far_depth < 0.001, "{far_depth}");
+}
+
+#[test]
+fn orthographic_depth_range() {
+    let projection = Projection::orthographic(10.0, 1.5, Z_NEAR, Z_FAR);
+    assert_near(depth(&projection, Z_NEAR), 0.0);
+    assert_near(depth(&projection, Z_FAR), 1.0);
+    // Linear in between
+    assert_near(depth(&projection, (Z_NEAR + Z_FAR) / 2.0), 0.5);
+
+    let projection = reversed(projection);
+    assert_near(depth(&projection, Z_NEAR), 1.0);
+    assert_near(depth(&projection, Z_FAR), 0.0);
+}
+
+#[test]
+fn perspective_field_of_view_and_aspect() {
+    let projection = Projection::perspective(90.0, 2.0, Z_NEAR, Z_FAR);
+    // With a 90 degree vertical field of view the top edge is at y = z, the right edge at x = 2z
+    let top = projection.matrix() * Vector4::new(0.0, 5.0, 5.0, 1.0);
+    let right = projection.matrix() * Vector4::new(10.0, 0.0, 5.0, 1.0);
+    assert_near(top.y / top.w, 1.0);
+    assert_near(right.x / right.w, 1.0);
+}
+
+#[test]
+fn orthographic_extent() {
+    let projection = Projection::orthographic(10.0, 2.0, Z_NEAR, Z_FAR);
+    let corner = projection.matrix() * Vector4::new(10.0, 5.0, 50.0, 1.0);
+    assert_near(corner.x / corner.w, 1.0);
+    assert_near(corner.y / corner.w, 1.0);
+}
+
+#[test]
+fn set_size_ignores_zero_size() {
+    let mut projection = Projection::default();
+    projection.set_size(1920, 1080);
+    assert_near(projection.aspect, 1920.0 / 1080.0);
+    projection.set_size(0, 1080);
+    assert_near(projection.aspect, 1920.0 / 1080.0);
+}
@@ -0,0 +1,168 @@
+// Picking rays and intersections, no graphics adapter needed
+
+use graphics_samples::camera::frustum::Plane;
+use graphics_samples::camera::orbit_camera::OrbitCamera;
+use graphics_samples::camera::projection::Projection;
+use graphics_samples::camera::ray::Ray;
+use graphics_samples::camera::{Camera, CameraTrait};
+use nalgebra::{Matrix4, Point3, Vector3};
+
+// Camera at (0, 0, -2) looking along +Z
+fn view_matrix() -> Matrix4<f32> {
+    Matrix4::look_at_lh(
+        &Point3::new(0.0, 0.0, -2.0),
+        &Point3::origin(),
+        &Vector3::y(),
+    )
+}
+
+fn assert_near(actual: Vector3<f32>, expected: [f32; 3]) {
+    assert!(
+        (actual - Vector3::from(expected)).norm() < 1.0e-3,
+        "{actual:?} != {expected:?}"
+    );
+}
+
+#[test]
+fn ray_from_screen() {
+    let perspective = Projection::perspective(90.0, 2.0, 0.1, 10.0);
+    let projections = [
+        perspective,
+        Projection {
+            reverse_z: true,
+            ..perspective
+        },
+        Projection::infinite_perspective(90.0, 2.0, 0.1),
+    ];
+    for projection in projections {
+        // Center of the screen
+        let ray = Ray::from_screen((100.0, 50.0), (200, 100), &view_matrix(), &projection).unwrap();
+        assert_near(ray.origin, [0.0, 0.0, -1.9]);
+        assert_near(ray.direction, [0.0, 0.0, 1.0]);
+
+        // Top right corner, 1 unit up and 2 (the aspect) to the right per unit forward
+        let ray = Ray::from_screen((200.0, 0.0), (200, 100), &view_matrix(), &projection).unwrap();
+        assert_near(
+            ray.direction,
+            Vector3::new(2.0, 1.0, 1.0).normalize().into(),
+        );
+    }
+
+    let orthographic = Projection::orthographic(2.0, 2.0, 0.1, 10.0);
+    let ray = Ray::from_screen((0.0, 100.0), (200, 100), &view_matrix(), &orthographic).unwrap();
+    assert_near(ray.origin, [-2.0, -1.0, -1.9]);
+    assert_near(ray.direction, [0.0, 0.0, 1.0]);
+
+    assert!(Ray::from_screen((0.0, 0.0), (0, 0), &view_matrix(), &orthographic).is_none());
+}
+
+#[test]
+fn ray_from_cursor_after_resize() {
+    let mut cameras: [Box<dyn CameraTrait>; 2] = [
+        Box::new(Camera::new(
+            [0.0, 0.0, -2.0],
+            [0.0, 0.0, 1.0],
+            1.0,
+            1.0,
+            None,
+        )),
+        Box::new(OrbitCamera::new([0.0, 0.0, 0.0], 2.0, 0.0, 0.0, 1.0, None)),
+    ];
+    for camera in cameras.iter_mut() {
+        // Like SampleApp after the window was resized from a square to 400x100
+        camera.sync_projection((100, 100), false);
+        camera.sync_projection((400, 100), true);
+        assert_eq!(camera.projection().aspect, 4.0);
+        assert!(camera.projection().reverse_z);
+
+        // The right edge is aspect times further out than the top edge
+        let tan_half_fov_y = 22.5_f32.to_radians().tan();
+        let right = camera.ray_from_cursor((400.0, 50.0), (400, 100)).unwrap();
+        assert_near(
+            right.direction,
+            Vector3::new(4.0 * tan_half_fov_y, 0.0, 1.0)
+                .normalize()
+                .into(),
+        );
+        let top = camera.ray_from_cursor((200.0, 0.0), (400, 100)).unwrap();
+        assert_near(
+            top.direction,
+            Vector3::new(0.0, tan_half_fov_y, 1.0).normalize().into(),
+        );
+    }
+}
+
+#[test]
+fn intersect_triangle() {
+    let (a, b, c) = ([0.0, 1.0, 1.0], [1.0, -1.0, 1.0], [-1.0, -1.0, 1.0]);
+    let ray = Ray::new([0.0, 0.0, -1.0], [0.0, 0.0, 1.0]);
+    assert_eq!(ray.intersect_triangle(a, b, c), Some(2.0));
+    // Both windings
+    assert_eq!(ray.intersect_triangle(a, c, b), Some(2.0));
+
+    assert!(
+        Ray::new([2.0, 0.0, -1.0], [0.0, 0.0, 1.0])
+            .intersect_triangle(a, b, c)
+            .is_none()
+    );
+    // Behind the origin
+    assert!(
+        Ray::new([0.0, 0.0, 2.0], [0.0, 0.0, 1.0])
+            .intersect_triangle(a, b, c)
+            .is_none()
+    );
+    // Parallel
+    assert!(
+        Ray::new([0.0, 0.0, 1.0], [1.0, 0.0, 0.0])
+            .intersect_triangle(a, b, c)
+            .is_none()
+    );
+}
+
+#[test]
+fn intersect_aabb() {
+    let (min, max) = ([-1.0, -1.0, -1.0], [1.0, 1.0, 1.0]);
+    assert_eq!(
+        Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, 1.0]).intersect_aabb(min, max),
+        Some(4.0)
+    );
+    assert_eq!(
+        Ray::new([0.0, 0.0, 0.0], [1.0, 1.0, 0.0]).intersect_aabb(min, max),
+        Some(0.0)
+    );
+    // Axis aligned ray beside the box
+    assert!(
+        Ray::new([2.0, 0.0, -5.0], [0.0, 0.0, 1.0])
+            .intersect_aabb(min, max)
+            .is_none()
+    );
+    assert!(
+        Ray::new([0.0, 0.0, -5.0], [0.0, 0.0, -1.0])
+            .intersect_aabb(min, max)
+            .is_none()
+    );
+    assert!(
+        Ray::new([0.0, 3.0, -5.0], [0.0, 0.0, 1.0])
+            .intersect_aabb(min, max)
+            .is_none()
+    );
+}
+
+#[test]
+fn intersect_plane() {
+    let ground = Plane::from_point_normal([0.0, -1.0, 0.0], [0.0, 1.0, 0.0]);
+    let ray = Ray::new([0.0, 1.0, 0.0], [0.0, -1.0, 1.0]);
+    let distance = ray.intersect_plane(&ground).unwrap();
+    assert_near(ray.at(distance), [0.0, -1.0, 2.0]);
+
+    assert!(
+        Ray::new([0.0, 1.0, 0.0], [0.0, 1.0, 0.0])
+            .intersect_plane(&ground)
+            .is_none()
+    );
+    assert!(
+        Ray::new([0.0, 1.0, 0.0], [1.0, 0.0, 0.0])
+            .intersect_plane(&ground)
+            .is_none()
+    );
+}